@@ -0,0 +1,43 @@
+use std::hint::black_box;
+
+use br_financial::{calculate_price_table, calculate_sac_table, MonthlyRate, RateContext};
+use criterion::{criterion_group, criterion_main, Criterion};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+/// A batch of scenarios sharing the same annual rate, as a pricing service would run.
+fn scenarios() -> Vec<(Decimal, u32)> {
+    (1..=20)
+        .flat_map(|step| [120, 240, 360].map(|months| (Decimal::from(step * 25_000), months)))
+        .collect()
+}
+
+fn batch(c: &mut Criterion) {
+    let scenarios = scenarios();
+    let mut group = c.benchmark_group("batch_same_rate");
+
+    group.bench_function("free_functions", |b| {
+        b.iter(|| {
+            for (amount, months) in &scenarios {
+                let rate = MonthlyRate::from_annual_percent(black_box(dec!(10.5))).unwrap();
+                black_box(calculate_price_table(*amount, rate, *months).unwrap());
+                black_box(calculate_sac_table(*amount, rate, *months).unwrap());
+            }
+        })
+    });
+
+    group.bench_function("rate_context", |b| {
+        b.iter(|| {
+            let context = RateContext::new(black_box(dec!(10.5))).unwrap().with_terms_up_to(360);
+            for (amount, months) in &scenarios {
+                black_box(context.price_table(*amount, *months).unwrap());
+                black_box(context.sac_table(*amount, *months).unwrap());
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, batch);
+criterion_main!(benches);
@@ -31,6 +31,7 @@
 //!         interest_per_year: dec!(10.5),
 //!         down_payment_percent: dec!(0),
 //!         total_months: 420,
+//!         signing_to_first_payment_months: 0,
 //!     };
 //!
 //!     match calculate_debt_trajectory(input) {
@@ -48,13 +49,97 @@
 //!     }
 //! }
 //! ```
+//!
+//! `DebtCalculationInput` covers the basic parameters. To persist a loan setup or use
+//! the optional features, build a `LoanConfig` and call `calculate` instead.
+
+#[cfg(feature = "chrono")]
+use std::collections::HashSet;
+
+#[cfg(feature = "chrono")]
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
 
 use serde::{Serialize, Deserialize};
 use rust_decimal::{ Decimal, MathematicalOps };
 use rust_decimal_macros::dec;
 
+/// Errors returned by the financing calculations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FinancingError {
+    /// The loan term is zero months.
+    ZeroMonths,
+    /// The annual rate (as a percentage) is at or below -100%, so `1 + rate` is not positive.
+    RateOutOfRange(Decimal),
+    /// An argument is outside the domain accepted by the calculation.
+    InvalidArgument(String),
+    /// The named input field is missing or does not hold a valid number.
+    InvalidNumber(String),
+}
+
+impl std::fmt::Display for FinancingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FinancingError::ZeroMonths => write!(f, "Total months cannot be zero."),
+            FinancingError::RateOutOfRange(rate) => {
+                write!(f, "Interest rate {}% is out of range; it must be above -100%.", rate)
+            }
+            FinancingError::InvalidArgument(message) => write!(f, "{}", message),
+            FinancingError::InvalidNumber(field) => {
+                write!(f, "Field `{}` is missing or is not a valid number.", field)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FinancingError {}
+
+/// The amortization systems supported by the library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AmortizationSystem {
+    /// Sistema de Amortização Constante: fixed amortization, declining installments.
+    Sac,
+    /// Sistema Francês de Amortização: fixed installments.
+    Price,
+}
+
+/// How interest is accrued within each month.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DayCount {
+    /// Every month is charged the effective monthly rate derived from the annual rate.
+    #[default]
+    Monthly30,
+    /// Each month is charged `balance * annual_rate * days_in_month / 365`, using the
+    /// annual percentage as a nominal rate and the calendar length of the installment's
+    /// month. The Price installment is solved on the same daily accrual.
+    Actual365,
+}
+
+/// Where the rounding residual of the grace-period interest goes when carência ends.
+///
+/// Grace installments are rounded to cents, so their sum differs from the interest
+/// actually accrued by a few cents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GraceTransition {
+    /// The residual, rounded to cents, is added to the interest of the last grace month,
+    /// so every grace installment stays in cents.
+    #[default]
+    PayInLastGraceMonth,
+    /// The residual is charged as accrued interest in the first amortizing installment.
+    RollIntoFirstInstallment,
+}
+
+/// How a prepayment is absorbed by the remaining schedule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PrepaymentStrategy {
+    /// Keep the installment and shorten the term.
+    #[default]
+    ReduceTerm,
+    /// Keep the term and lower the installments.
+    ReduceInstallment,
+}
+
 /// Input parameters for debt trajectory calculation.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DebtCalculationInput {
     /// The total principal amount of the loan.
     pub total_amount: Decimal,
@@ -65,56 +150,736 @@ pub struct DebtCalculationInput {
     pub down_payment_percent: Decimal,
     /// The total number of months for the loan.
     pub total_months: u32,
+    /// Months between signing and the first installment during which interest accrues
+    /// on the full financed amount. The accrued interest is not capitalized; it is
+    /// reported separately and spread across the first installments.
+    #[serde(default)]
+    pub signing_to_first_payment_months: u32,
+}
+
+/// Reads a decimal from a JSON number or numeric string.
+#[cfg(feature = "json")]
+fn json_decimal(value: &serde_json::Value, field: &str) -> Result<Decimal, FinancingError> {
+    use std::str::FromStr;
+
+    let text = match value.get(field) {
+        Some(serde_json::Value::Number(number)) => number.to_string(),
+        Some(serde_json::Value::String(text)) => text.trim().to_string(),
+        _ => return Err(FinancingError::InvalidNumber(field.to_string())),
+    };
+
+    Decimal::from_str(&text)
+        .or_else(|_| Decimal::from_scientific(&text))
+        .map_err(|_| FinancingError::InvalidNumber(field.to_string()))
+}
+
+/// Reads a non-negative whole number from a JSON number or numeric string.
+#[cfg(feature = "json")]
+fn json_u32(value: &serde_json::Value, field: &str) -> Result<u32, FinancingError> {
+    use rust_decimal::prelude::ToPrimitive;
+
+    let number = json_decimal(value, field)?;
+    if !number.fract().is_zero() {
+        return Err(FinancingError::InvalidNumber(field.to_string()));
+    }
+
+    number.to_u32().ok_or_else(|| FinancingError::InvalidNumber(field.to_string()))
+}
+
+/// Builds an input from loosely-typed JSON, accepting numbers or numeric strings.
+///
+/// `total_amount`, `interest_per_year` and `total_months` are required;
+/// `down_payment_percent` and `signing_to_first_payment_months` default to zero.
+#[cfg(feature = "json")]
+impl TryFrom<serde_json::Value> for DebtCalculationInput {
+    type Error = FinancingError;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        let optional_decimal = |field: &str| match value.get(field) {
+            None | Some(serde_json::Value::Null) => Ok(dec!(0)),
+            Some(_) => json_decimal(&value, field),
+        };
+        let optional_u32 = |field: &str| match value.get(field) {
+            None | Some(serde_json::Value::Null) => Ok(0),
+            Some(_) => json_u32(&value, field),
+        };
+
+        Ok(DebtCalculationInput {
+            total_amount: json_decimal(&value, "total_amount")?,
+            interest_per_year: json_decimal(&value, "interest_per_year")?,
+            down_payment_percent: optional_decimal("down_payment_percent")?,
+            total_months: json_u32(&value, "total_months")?,
+            signing_to_first_payment_months: optional_u32("signing_to_first_payment_months")?,
+        })
+    }
+}
+
+/// The complete configuration of a loan, used as the single input to `calculate`.
+///
+/// It supersedes `DebtCalculationInput`: every optional feature has a serde default, so
+/// serialized configurations stay readable as new features are added.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoanConfig {
+    /// The total principal amount of the loan.
+    pub total_amount: Decimal,
+    /// The annual interest rate as a percentage (e.g., 10.5 for 10.5%).
+    pub interest_per_year: Decimal,
+    /// The percentage of the total amount that is provided as a down payment.
+    #[serde(default)]
+    pub down_payment_percent: Decimal,
+    /// The total number of months for the loan.
+    pub total_months: u32,
+    /// Months between signing and the first installment during which interest accrues
+    /// on the full financed amount, reported apart from the schedule.
+    #[serde(default)]
+    pub signing_to_first_payment_months: u32,
+    /// Administrative fee charged with each installment, indexed by month (zero-based).
+    /// A shorter schedule is padded with its last value, so a single entry is a flat fee.
+    #[serde(default)]
+    pub monthly_fees: Vec<Decimal>,
+    /// Outstanding balance at or below which the loan is settled by the current
+    /// installment, clearing rounding residuals. Defaults to one cent.
+    #[serde(default = "default_settlement_threshold")]
+    pub settlement_threshold: Decimal,
+    /// Extra principal paid with every installment (aporte mensal), shortening the term.
+    #[serde(default)]
+    pub monthly_extra: Option<Decimal>,
+    /// Upfront subsidy (subsídio, e.g. Minha Casa Minha Vida) deducted from the financed
+    /// amount.
+    #[serde(default)]
+    pub subsidy_upfront: Option<Decimal>,
+    /// Subsidized reduction of the monthly rate, as a decimal (0.001 lowers a 0.8% monthly
+    /// rate to 0.7%). The effective rate never goes below zero.
+    #[serde(default)]
+    pub subsidy_monthly_rate_reduction: Option<Decimal>,
+    /// How each month's interest is accrued. Defaults to the flat monthly factor.
+    #[serde(default)]
+    pub day_count: DayCount,
+    /// The calendar month of the first installment, required by `DayCount::Actual365`
+    /// to know each month's length.
+    #[serde(default)]
+    pub first_payment_month: Option<YearMonth>,
+    /// Whether the first Price installment is paid at signing (annuity-due) instead of
+    /// at the end of the first month. The installment becomes `PMT / (1 + i)` and the
+    /// first one carries no interest. SAC is not affected. Not available with
+    /// `DayCount::Actual365`.
+    #[serde(default)]
+    pub annuity_due: bool,
+    /// Months of interest-only installments (carência) before amortization starts. They
+    /// are added before the `total_months` amortizing installments.
+    #[serde(default)]
+    pub grace_months: u32,
+    /// Where the cent-rounding residual of the grace interest is paid.
+    #[serde(default)]
+    pub grace_transition: GraceTransition,
+    /// Lump-sum prepayments as `(month, amount)` pairs, with the month 1-based among the
+    /// amortizing installments, i.e. after any grace period.
+    #[serde(default)]
+    pub prepayments: Vec<(u32, Decimal)>,
+    /// How the prepayments are absorbed by the remaining schedule.
+    #[serde(default)]
+    pub prepayment_strategy: PrepaymentStrategy,
+    /// Monthly MIP insurance rate as a decimal, charged on each month's opening balance.
+    #[serde(default)]
+    pub mip_rate: Option<Decimal>,
+    /// Whether the whole MIP premium is paid at signing instead of monthly. The sum of
+    /// the would-be monthly premiums is reported in each table's `upfront_insurance` and
+    /// left out of the installments.
+    #[serde(default)]
+    pub insurance_upfront: bool,
+    /// Whether the upfront MIP premium is rolled into the principal instead of paid at
+    /// signing. Each table finances its own premium, priced on the schedule without it,
+    /// while `financed_amount` still reports the loan alone. Requires `insurance_upfront`.
+    #[serde(default)]
+    pub finance_insurance_upfront: bool,
+    /// Granularity the Price installment is rounded up to (e.g. 1 for whole reais). The
+    /// overpayment amortizes the loan faster, which may pay it off early. Not available
+    /// with `seasonal_weights`, whose installments vary by month.
+    #[serde(default)]
+    pub round_installment_to: Option<Decimal>,
+    /// The lowest first installment the lender accepts. A lower Price installment, or SAC
+    /// first installment, is raised to it and the extra principal shortens the term; the
+    /// effective term is reported in `settled_at_month`.
+    #[serde(default)]
+    pub min_first_installment: Option<Decimal>,
+    /// Seasonal weights of the Price installment by calendar month, January first. Must
+    /// hold 12 non-negative values summing to 12; empty keeps the standard Price table.
+    /// Without `first_payment_month` the first installment is taken to fall in January.
+    /// Not available with `annuity_due` or `DayCount::Actual365`.
+    #[serde(default)]
+    pub seasonal_weights: Vec<Decimal>,
+    /// The length of the last period as a fraction of a month, strictly between 0 and 1,
+    /// for terms that end mid-month. The last installment accrues interest only for that
+    /// fraction and settles the balance. It is applied after every other adjustment, and
+    /// only to schedules that still run the full term.
+    #[serde(default)]
+    pub final_stub_fraction: Option<Decimal>,
+    /// A fixed monthly housing benefit an employer pays against each installment. The
+    /// installments and totals stay gross; each month's `employer_subsidy` holds the part
+    /// covered, clamped to the installment, and `net_installment` the borrower's share.
+    #[serde(default)]
+    pub employer_subsidy_monthly: Option<Decimal>,
+}
+
+fn default_settlement_threshold() -> Decimal {
+    dec!(0.01)
+}
+
+impl Default for LoanConfig {
+    fn default() -> Self {
+        LoanConfig {
+            total_amount: dec!(0),
+            interest_per_year: dec!(0),
+            down_payment_percent: dec!(0),
+            total_months: 0,
+            signing_to_first_payment_months: 0,
+            monthly_fees: Vec::new(),
+            settlement_threshold: default_settlement_threshold(),
+            monthly_extra: None,
+            subsidy_upfront: None,
+            subsidy_monthly_rate_reduction: None,
+            day_count: DayCount::default(),
+            first_payment_month: None,
+            annuity_due: false,
+            grace_months: 0,
+            grace_transition: GraceTransition::default(),
+            prepayments: Vec::new(),
+            prepayment_strategy: PrepaymentStrategy::default(),
+            mip_rate: None,
+            insurance_upfront: false,
+            finance_insurance_upfront: false,
+            round_installment_to: None,
+            min_first_installment: None,
+            seasonal_weights: Vec::new(),
+            final_stub_fraction: None,
+            employer_subsidy_monthly: None,
+        }
+    }
+}
+
+impl From<DebtCalculationInput> for LoanConfig {
+    fn from(input: DebtCalculationInput) -> Self {
+        LoanConfig {
+            total_amount: input.total_amount,
+            interest_per_year: input.interest_per_year,
+            down_payment_percent: input.down_payment_percent,
+            total_months: input.total_months,
+            signing_to_first_payment_months: input.signing_to_first_payment_months,
+            ..Default::default()
+        }
+    }
 }
 
 /// Represents the payment details for a single month.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
 pub struct MonthPayment {
     /// The remaining balance of the loan after the payment.
+    #[serde(serialize_with = "json_decimal::serialize")]
     pub new_balance: Decimal,
-    /// The portion of the payment that goes towards reducing the principal.
+    /// The portion of the payment that goes towards reducing the principal. This is
+    /// strictly principal reduction and never includes fees.
+    #[serde(serialize_with = "json_decimal::serialize")]
     pub current_amortization: Decimal,
     /// The portion of the payment that covers interest.
-    pub current_interest: Decimal
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub current_interest: Decimal,
+    /// The share of the signing-to-first-payment accrued interest charged this month, plus
+    /// any grace interest residual rolled into it (see `GraceTransition`).
+    #[serde(default, serialize_with = "json_decimal::serialize")]
+    pub accrued_interest: Decimal,
+    /// The administrative fee charged with this month's installment.
+    #[serde(default, serialize_with = "json_decimal::serialize")]
+    pub admin_fee: Decimal,
+    /// The MIP insurance premium charged with this month's installment.
+    #[serde(default, serialize_with = "json_decimal::serialize")]
+    pub insurance: Decimal,
+    /// The cumulative principal repaid up to and including this month. Unlike the
+    /// installment, it never includes interest, fees or insurance, and it reaches the
+    /// financed amount in the final month.
+    #[serde(default, serialize_with = "json_decimal::serialize")]
+    pub principal_paid_to_date: Decimal,
+    /// The installment discounted back to time zero, when populated by
+    /// `with_present_value`.
+    #[serde(default, skip_serializing_if = "Option::is_none", serialize_with = "json_decimal::serialize_option")]
+    pub present_value: Option<Decimal>,
+    /// The monthly rate actually charged, after any floor or ceiling, when populated by
+    /// `calculate_with_rate_path`.
+    #[serde(default, skip_serializing_if = "Option::is_none", serialize_with = "json_decimal::serialize_rate_option")]
+    pub applied_rate: Option<Decimal>,
+    /// The part of the installment paid by an employer housing benefit, see
+    /// `LoanConfig::employer_subsidy_monthly`. It never exceeds the installment.
+    #[serde(default, serialize_with = "json_decimal::serialize")]
+    pub employer_subsidy: Decimal,
+}
+
+impl MonthPayment {
+    /// Creates a payment from its core figures. Accrued interest, fees, insurance and the
+    /// cumulative principal start at zero, and the optional fields are unset, so callers
+    /// fabricating payments are not affected when fields are added.
+    ///
+    /// # Arguments
+    ///
+    /// * `new_balance` - The balance after the payment.
+    /// * `current_amortization` - The principal repaid this month.
+    /// * `current_interest` - The interest charged this month.
+    pub fn new(new_balance: Decimal, current_amortization: Decimal, current_interest: Decimal) -> Self {
+        MonthPayment {
+            new_balance,
+            current_amortization,
+            current_interest,
+            accrued_interest: dec!(0),
+            admin_fee: dec!(0),
+            insurance: dec!(0),
+            principal_paid_to_date: dec!(0),
+            present_value: None,
+            applied_rate: None,
+            employer_subsidy: dec!(0),
+        }
+    }
+
+    /// Returns the full installment of the month: amortization, interest, accrued interest,
+    /// fees and insurance.
+    pub fn installment(&self) -> Decimal {
+        self.current_amortization + self.current_interest + self.accrued_interest + self.admin_fee + self.insurance
+    }
+
+    /// Returns what the borrower pays out of pocket this month: the installment minus the
+    /// employer subsidy.
+    pub fn net_installment(&self) -> Decimal {
+        self.installment() - self.employer_subsidy
+    }
+
+    /// Returns the outstanding balance before this month's payment.
+    pub fn opening_balance(&self) -> Decimal {
+        self.new_balance + self.current_amortization
+    }
+
+    /// Returns the monthly rate effectively charged, `current_interest / opening_balance`,
+    /// or zero when the opening balance is zero.
+    pub fn realized_rate(&self) -> Decimal {
+        let opening_balance = self.opening_balance();
+        if opening_balance.is_zero() {
+            return dec!(0);
+        }
+
+        self.current_interest / opening_balance
+    }
+
+    /// Returns whether every Decimal field is within `tolerance` of `other`'s, absorbing
+    /// rounding differences.
+    pub fn approx_eq(&self, other: &MonthPayment, tolerance: Decimal) -> bool {
+        let close = |a: Decimal, b: Decimal| (a - b).abs() <= tolerance;
+        close(self.new_balance, other.new_balance)
+            && close(self.current_amortization, other.current_amortization)
+            && close(self.current_interest, other.current_interest)
+            && close(self.accrued_interest, other.accrued_interest)
+            && close(self.admin_fee, other.admin_fee)
+            && close(self.insurance, other.insurance)
+            && close(self.principal_paid_to_date, other.principal_paid_to_date)
+            && close(self.employer_subsidy, other.employer_subsidy)
+            && match (self.present_value, other.present_value) {
+                (Some(a), Some(b)) => close(a, b),
+                (a, b) => a == b,
+            }
+            && match (self.applied_rate, other.applied_rate) {
+                (Some(a), Some(b)) => close(a, b),
+                (a, b) => a == b,
+            }
+    }
+
+    /// Returns the payment with every monetary field multiplied by `fx_rate`, leaving
+    /// `applied_rate` untouched.
+    pub fn to_currency(&self, fx_rate: Decimal) -> Self {
+        MonthPayment {
+            new_balance: self.new_balance * fx_rate,
+            current_amortization: self.current_amortization * fx_rate,
+            current_interest: self.current_interest * fx_rate,
+            accrued_interest: self.accrued_interest * fx_rate,
+            admin_fee: self.admin_fee * fx_rate,
+            insurance: self.insurance * fx_rate,
+            principal_paid_to_date: self.principal_paid_to_date * fx_rate,
+            present_value: self.present_value.map(|value| value * fx_rate),
+            applied_rate: self.applied_rate,
+            employer_subsidy: self.employer_subsidy * fx_rate,
+        }
+    }
+}
+
+/// Converts every payment of a curve with `MonthPayment::to_currency`.
+fn curve_to_currency(amortization_curve: &[MonthPayment], fx_rate: Decimal) -> Vec<MonthPayment> {
+    amortization_curve.iter().map(|payment| payment.to_currency(fx_rate)).collect()
+}
+
+/// Divides the total interest of a curve by its principal, the first opening balance.
+fn curve_interest_ratio(amortization_curve: &[MonthPayment]) -> Decimal {
+    match amortization_curve.first().map(MonthPayment::opening_balance) {
+        Some(principal) if !principal.is_zero() => total_interest(amortization_curve) / principal,
+        _ => dec!(0),
+    }
+}
+
+/// Divides a total paid by the principal of its curve, the first opening balance.
+fn cost_multiple(amortization_curve: &[MonthPayment], total_paid: Decimal) -> Decimal {
+    match amortization_curve.first().map(MonthPayment::opening_balance) {
+        Some(principal) if !principal.is_zero() => total_paid / principal,
+        _ => dec!(0),
+    }
+}
+
+/// Compares two curves row by row with `MonthPayment::approx_eq`.
+fn curves_approx_eq(a: &[MonthPayment], b: &[MonthPayment], tolerance: Decimal) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.approx_eq(b, tolerance))
 }
 
 /// Contains the results of a financing calculation using the Price table method.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
 pub struct PriceTableResult {
     /// The fixed monthly payment amount.
+    #[serde(serialize_with = "json_decimal::serialize")]
     pub fixed_payment: Decimal,
     /// The total amount paid over the lifetime of the loan.
+    #[serde(serialize_with = "json_decimal::serialize")]
     pub total_paid: Decimal,
+    /// The total interest paid over the lifetime of the loan, including accrued signing
+    /// interest.
+    #[serde(default, serialize_with = "json_decimal::serialize")]
+    pub total_interest: Decimal,
     /// A vector containing the payment details for each month.
     pub amortization_curve: Vec<MonthPayment>,
+    /// The month (1-based) in which the loan was paid off, when that happened before the
+    /// end of the term (extra payments, or a balance within the settlement threshold).
+    #[serde(default)]
+    pub settled_at_month: Option<u32>,
+    /// The interest saved by the monthly extra payments, compared to the contracted schedule.
+    #[serde(default, serialize_with = "json_decimal::serialize")]
+    pub interest_saved: Decimal,
+    /// The insurance premium paid at signing instead of monthly, when
+    /// `LoanConfig::insurance_upfront` is set, or added to the principal with
+    /// `LoanConfig::finance_insurance_upfront`.
+    #[serde(default, serialize_with = "json_decimal::serialize")]
+    pub upfront_insurance: Decimal,
 }
 
 /// Contains the results of a financing calculation using the SAC method.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
 pub struct SacTableResult {
     /// The fixed amount of principal paid off each month.
+    #[serde(serialize_with = "json_decimal::serialize")]
     pub fixed_amortization: Decimal,
     /// The amount of the first payment, which is the highest.
+    #[serde(serialize_with = "json_decimal::serialize")]
     pub first_payment: Decimal,
     /// The amount of the last payment, which is the lowest.
+    #[serde(serialize_with = "json_decimal::serialize")]
     pub last_payment: Decimal,
     /// The total amount paid over the lifetime of the loan.
+    #[serde(serialize_with = "json_decimal::serialize")]
     pub total_paid: Decimal,
+    /// The total interest paid over the lifetime of the loan, including accrued signing
+    /// interest.
+    #[serde(default, serialize_with = "json_decimal::serialize")]
+    pub total_interest: Decimal,
     /// A vector containing the payment details for each month.
     pub amortization_curve: Vec<MonthPayment>,
+    /// The month (1-based) in which the loan was paid off, when that happened before the
+    /// end of the term (extra payments, or a balance within the settlement threshold).
+    #[serde(default)]
+    pub settled_at_month: Option<u32>,
+    /// The interest saved by the monthly extra payments, compared to the contracted schedule.
+    #[serde(default, serialize_with = "json_decimal::serialize")]
+    pub interest_saved: Decimal,
+    /// The insurance premium paid at signing instead of monthly, when
+    /// `LoanConfig::insurance_upfront` is set, or added to the principal with
+    /// `LoanConfig::finance_insurance_upfront`.
+    #[serde(default, serialize_with = "json_decimal::serialize")]
+    pub upfront_insurance: Decimal,
+}
+
+impl PriceTableResult {
+    /// Recomputes `total_paid` from the curve after installments were adjusted.
+    fn recompute_totals(&mut self) {
+        self.total_paid = self.amortization_curve.iter().map(MonthPayment::installment).sum::<Decimal>().round_dp(2);
+        self.total_interest = total_interest(&self.amortization_curve).round_dp(2);
+    }
+
+    /// Returns whether every Decimal field, including the curve's, is within `tolerance`
+    /// of `other`'s. `settled_at_month` must match exactly.
+    pub fn approx_eq(&self, other: &PriceTableResult, tolerance: Decimal) -> bool {
+        let close = |a: Decimal, b: Decimal| (a - b).abs() <= tolerance;
+        close(self.fixed_payment, other.fixed_payment)
+            && close(self.total_paid, other.total_paid)
+            && close(self.total_interest, other.total_interest)
+            && close(self.interest_saved, other.interest_saved)
+            && close(self.upfront_insurance, other.upfront_insurance)
+            && self.settled_at_month == other.settled_at_month
+            && curves_approx_eq(&self.amortization_curve, &other.amortization_curve, tolerance)
+    }
+
+    /// Returns the total interest as a fraction of the principal, e.g. 1.2 when the
+    /// interest paid is 120% of the amount financed. Returns zero for an empty curve.
+    pub fn interest_ratio(&self) -> Decimal {
+        curve_interest_ratio(&self.amortization_curve)
+    }
+
+    /// Returns the total the borrower pays out of pocket, net of the employer subsidy,
+    /// rounded to cents.
+    pub fn total_out_of_pocket(&self) -> Decimal {
+        self.amortization_curve.iter().map(MonthPayment::net_installment).sum::<Decimal>().round_dp(2)
+    }
+
+    /// Returns how many times over the principal is repaid, the total paid (with fees and
+    /// insurance, including any upfront insurance) divided by the principal, the first
+    /// opening balance. Returns zero for an empty curve.
+    pub fn cost_multiple(&self) -> Decimal {
+        cost_multiple(&self.amortization_curve, self.total_paid + self.upfront_insurance)
+    }
+
+    /// Returns the table expressed in another currency: every monetary field, including
+    /// the curve's, is multiplied by the fixed `fx_rate`. The term is unchanged.
+    pub fn to_currency(&self, fx_rate: Decimal) -> Self {
+        PriceTableResult {
+            fixed_payment: self.fixed_payment * fx_rate,
+            total_paid: self.total_paid * fx_rate,
+            total_interest: self.total_interest * fx_rate,
+            amortization_curve: curve_to_currency(&self.amortization_curve, fx_rate),
+            settled_at_month: self.settled_at_month,
+            interest_saved: self.interest_saved * fx_rate,
+            upfront_insurance: self.upfront_insurance * fx_rate,
+        }
+    }
+}
+
+impl SacTableResult {
+    /// Recomputes `total_paid` and the first and last payments from the curve after
+    /// installments were adjusted.
+    fn recompute_totals(&mut self) {
+        let curve = &self.amortization_curve;
+        self.first_payment = curve.first().map(MonthPayment::installment).unwrap_or_default().round_dp(2);
+        self.last_payment = curve.last().map(MonthPayment::installment).unwrap_or_default().round_dp(2);
+        self.total_paid = curve.iter().map(MonthPayment::installment).sum::<Decimal>().round_dp(2);
+        self.total_interest = total_interest(curve).round_dp(2);
+    }
+
+    /// Returns whether every Decimal field, including the curve's, is within `tolerance`
+    /// of `other`'s. `settled_at_month` must match exactly.
+    pub fn approx_eq(&self, other: &SacTableResult, tolerance: Decimal) -> bool {
+        let close = |a: Decimal, b: Decimal| (a - b).abs() <= tolerance;
+        close(self.fixed_amortization, other.fixed_amortization)
+            && close(self.first_payment, other.first_payment)
+            && close(self.last_payment, other.last_payment)
+            && close(self.total_paid, other.total_paid)
+            && close(self.total_interest, other.total_interest)
+            && close(self.interest_saved, other.interest_saved)
+            && close(self.upfront_insurance, other.upfront_insurance)
+            && self.settled_at_month == other.settled_at_month
+            && curves_approx_eq(&self.amortization_curve, &other.amortization_curve, tolerance)
+    }
+
+    /// Returns the total interest as a fraction of the principal, e.g. 1.2 when the
+    /// interest paid is 120% of the amount financed. Returns zero for an empty curve.
+    pub fn interest_ratio(&self) -> Decimal {
+        curve_interest_ratio(&self.amortization_curve)
+    }
+
+    /// Returns the total the borrower pays out of pocket, net of the employer subsidy,
+    /// rounded to cents.
+    pub fn total_out_of_pocket(&self) -> Decimal {
+        self.amortization_curve.iter().map(MonthPayment::net_installment).sum::<Decimal>().round_dp(2)
+    }
+
+    /// Returns how many times over the principal is repaid, the total paid (with fees and
+    /// insurance, including any upfront insurance) divided by the principal, the first
+    /// opening balance. Returns zero for an empty curve.
+    pub fn cost_multiple(&self) -> Decimal {
+        cost_multiple(&self.amortization_curve, self.total_paid + self.upfront_insurance)
+    }
+
+    /// Returns the table expressed in another currency: every monetary field, including
+    /// the curve's, is multiplied by the fixed `fx_rate`. The term is unchanged.
+    pub fn to_currency(&self, fx_rate: Decimal) -> Self {
+        SacTableResult {
+            fixed_amortization: self.fixed_amortization * fx_rate,
+            first_payment: self.first_payment * fx_rate,
+            last_payment: self.last_payment * fx_rate,
+            total_paid: self.total_paid * fx_rate,
+            total_interest: self.total_interest * fx_rate,
+            amortization_curve: curve_to_currency(&self.amortization_curve, fx_rate),
+            settled_at_month: self.settled_at_month,
+            interest_saved: self.interest_saved * fx_rate,
+            upfront_insurance: self.upfront_insurance * fx_rate,
+        }
+    }
+}
+
+/// The subsidies applied to a loan, reported apart from the schedules.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct SubsidyReport {
+    /// The upfront subsidy deducted from the financed amount.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub upfront: Decimal,
+    /// The reduction of the monthly rate, as a decimal.
+    #[serde(serialize_with = "json_decimal::serialize_rate")]
+    pub monthly_rate_reduction: Decimal,
+    /// The monthly rate actually charged after the reduction.
+    #[serde(serialize_with = "json_decimal::serialize_rate")]
+    pub effective_monthly_rate: Decimal,
 }
 
 /// Contains the comprehensive results for both Price and SAC table calculations.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
 pub struct DebtTrajectoryResult {
     /// The initial total amount of the loan.
+    #[serde(serialize_with = "json_decimal::serialize")]
     pub financed_amount: Decimal,
+    /// The effective monthly interest rate applied to the schedules, as a decimal.
+    #[serde(default, serialize_with = "json_decimal::serialize_rate")]
+    pub monthly_interest_rate: Decimal,
+    /// Interest accrued between signing and the first installment, charged apart from
+    /// the amortization schedule.
+    #[serde(default, serialize_with = "json_decimal::serialize")]
+    pub accrued_interest: Decimal,
+    /// The subsidies applied to the loan, when any was configured.
+    #[serde(default)]
+    pub subsidy: Option<SubsidyReport>,
+    /// How the grace interest residual was settled, when a grace period was configured.
+    #[serde(default)]
+    pub grace_transition: Option<GraceTransition>,
     /// The results calculated using the Price table method.
     pub price_table: PriceTableResult,
     /// The results calculated using the SAC method.
     pub sac_table: SacTableResult,
 }
 
+/// The headline figures of a `DebtTrajectoryResult` for both systems, flattened for APIs
+/// and templates.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct TrajectorySummary {
+    /// The amount financed after the down payment and subsidies.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub financed_amount: Decimal,
+    /// The effective monthly interest rate, as a decimal.
+    #[serde(serialize_with = "json_decimal::serialize_rate")]
+    pub monthly_interest_rate: Decimal,
+    /// Interest accrued between signing and the first installment.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub accrued_interest: Decimal,
+    /// The fixed Price installment.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub price_fixed_payment: Decimal,
+    /// The total paid under Price.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub price_total_paid: Decimal,
+    /// The total interest paid under Price.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub price_total_interest: Decimal,
+    /// The constant SAC amortization.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub sac_fixed_amortization: Decimal,
+    /// The first SAC installment.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub sac_first_payment: Decimal,
+    /// The last SAC installment.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub sac_last_payment: Decimal,
+    /// The total paid under SAC.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub sac_total_paid: Decimal,
+    /// The total interest paid under SAC.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub sac_total_interest: Decimal,
+}
+
+impl DebtTrajectoryResult {
+    /// Renders a stable, line-oriented dump of the result for snapshot tests: the scalar
+    /// fields, then the Price and SAC sections from their own `to_snapshot_string`.
+    pub fn to_snapshot_string(&self) -> String {
+        let mut snapshot = format!(
+            "financed_amount: {:.2}\nmonthly_interest_rate: {:.10}\naccrued_interest: {:.2}\nsubsidy: {}\n\
+             grace_transition: {:?}\n",
+            self.financed_amount,
+            self.monthly_interest_rate,
+            self.accrued_interest,
+            self.subsidy.as_ref().map_or("none".to_string(), |subsidy| format!(
+                "upfront {:.2} monthly_rate_reduction {:.10} effective_monthly_rate {:.10}",
+                subsidy.upfront, subsidy.monthly_rate_reduction, subsidy.effective_monthly_rate
+            )),
+            self.grace_transition,
+        );
+        snapshot.push_str("[price]\n");
+        snapshot.push_str(&self.price_table.to_snapshot_string());
+        snapshot.push_str("[sac]\n");
+        snapshot.push_str(&self.sac_table.to_snapshot_string());
+        snapshot
+    }
+
+    /// Returns whether every Decimal field of both results, including the tables and their
+    /// curves, is within `tolerance` of `other`'s.
+    pub fn approx_eq(&self, other: &DebtTrajectoryResult, tolerance: Decimal) -> bool {
+        let close = |a: Decimal, b: Decimal| (a - b).abs() <= tolerance;
+        let subsidy_close = match (&self.subsidy, &other.subsidy) {
+            (Some(a), Some(b)) => {
+                close(a.upfront, b.upfront)
+                    && close(a.monthly_rate_reduction, b.monthly_rate_reduction)
+                    && close(a.effective_monthly_rate, b.effective_monthly_rate)
+            }
+            (a, b) => a.is_none() && b.is_none(),
+        };
+
+        close(self.financed_amount, other.financed_amount)
+            && close(self.monthly_interest_rate, other.monthly_interest_rate)
+            && close(self.accrued_interest, other.accrued_interest)
+            && subsidy_close
+            && self.grace_transition == other.grace_transition
+            && self.price_table.approx_eq(&other.price_table, tolerance)
+            && self.sac_table.approx_eq(&other.sac_table, tolerance)
+    }
+
+    /// Returns the result expressed in another currency, e.g. for comparing a BRL loan in
+    /// a home currency. Amounts are multiplied by the fixed `fx_rate` and not re-rounded;
+    /// rates and the term are left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `fx_rate` - Units of the target currency per BRL.
+    pub fn to_currency(&self, fx_rate: Decimal) -> Self {
+        DebtTrajectoryResult {
+            financed_amount: self.financed_amount * fx_rate,
+            monthly_interest_rate: self.monthly_interest_rate,
+            accrued_interest: self.accrued_interest * fx_rate,
+            subsidy: self.subsidy.as_ref().map(|subsidy| SubsidyReport {
+                upfront: subsidy.upfront * fx_rate,
+                ..subsidy.clone()
+            }),
+            grace_transition: self.grace_transition,
+            price_table: self.price_table.to_currency(fx_rate),
+            sac_table: self.sac_table.to_currency(fx_rate),
+        }
+    }
+
+    /// Returns the headline figures of both systems as a flat `TrajectorySummary`.
+    pub fn summary(&self) -> TrajectorySummary {
+        TrajectorySummary {
+            financed_amount: self.financed_amount,
+            monthly_interest_rate: self.monthly_interest_rate,
+            accrued_interest: self.accrued_interest,
+            price_fixed_payment: self.price_table.fixed_payment,
+            price_total_paid: self.price_table.total_paid,
+            price_total_interest: self.price_table.total_interest,
+            sac_fixed_amortization: self.sac_table.fixed_amortization,
+            sac_first_payment: self.sac_table.first_payment,
+            sac_last_payment: self.sac_table.last_payment,
+            sac_total_paid: self.sac_table.total_paid,
+            sac_total_interest: self.sac_table.total_interest,
+        }
+    }
+}
+
 /// Calculates the down payment amount based on a total amount and a percentage.
 ///
 /// This function ensures that the calculated down payment does not exceed the
@@ -132,30 +897,56 @@ pub fn clean_down_payment(initial_amount: Decimal, percent: Decimal) -> Decimal
     let one = Decimal::from_str_exact("1").unwrap();
     let norm_percent = percent / Decimal::from_str_exact("100.0").unwrap();
 
-    return initial_amount*(one - norm_percent);
+    initial_amount*(one - norm_percent)
 }
 
 /// Normalizes an annual interest rate percentage to a monthly decimal factor.
 ///
 /// This function converts a rate like 10.5% per year into its equivalent monthly multiplier
 /// for use in compound interest calculations.
+///
+/// Rates at or below -100% have no monthly equivalent, since `1 + rate` is not positive,
+/// and give -1 instead of reaching `powd`. Use `MonthlyRate::from_annual_percent` or
+/// `normalize_rate` to reject them with `FinancingError::RateOutOfRange`.
 pub fn normalize_annual_interest_rate(input: Decimal) -> Decimal {
+    normalize_rate(input, 12).unwrap_or(dec!(-1))
+}
+
+/// Normalizes an annual interest rate percentage to the rate of an arbitrary
+/// capitalization period.
+///
+/// The per-period rate is `(1 + annual)^(1 / periods_per_year) - 1`, so 12 gives the
+/// monthly rate, 4 the quarterly rate and 252 the rate per business day.
+///
+/// # Errors
+///
+/// Returns `FinancingError::RateOutOfRange` if the rate is at or below -100%, since the
+/// fractional power of a non-positive base is undefined, and
+/// `FinancingError::InvalidArgument` if `periods_per_year` is zero.
+pub fn normalize_rate(annual_percent: Decimal, periods_per_year: u32) -> Result<Decimal, FinancingError> {
+    if periods_per_year == 0 {
+        return Err(FinancingError::InvalidArgument("Periods per year cannot be zero.".to_string()));
+    }
+
     let one = Decimal::from_str_exact("1").unwrap();
-    let percent = input / Decimal::from_str_exact("100.0").unwrap();
-    let twelve = Decimal::from_str_exact("12").unwrap();
+    let percent = annual_percent / Decimal::from_str_exact("100.0").unwrap();
+    let periods = Decimal::from(periods_per_year);
 
     let base = one + percent;
-    let exponent  = one / twelve;
+    if base <= Decimal::ZERO {
+        return Err(FinancingError::RateOutOfRange(annual_percent));
+    }
+    let exponent  = one / periods;
 
     let power_result = base.powd(exponent);
 
-    return power_result - one;
+    Ok(power_result - one)
 }
 
 /// Calculates and compares the debt trajectory for both Price and SAC amortization systems.
 ///
-/// This is the main entry point of the library. It takes the loan parameters and
-/// returns a struct containing detailed results for both financing tables.
+/// This takes the flat `DebtCalculationInput` and is equivalent to calling `calculate`
+/// with the corresponding `LoanConfig`.
 ///
 /// # Arguments
 ///
@@ -163,25 +954,307 @@ pub fn normalize_annual_interest_rate(input: Decimal) -> Decimal {
 ///
 /// # Errors
 ///
-/// Returns an error if the `total_months` is zero.
+/// Returns an error if the `total_months` is zero or the interest rate is at or below -100%.
+/// The error wraps a `FinancingError`, which `calculate` returns directly.
 pub fn calculate_debt_trajectory(input: DebtCalculationInput) -> Result<DebtTrajectoryResult, anyhow::Error> {
+    Ok(calculate(&LoanConfig::from(input))?)
+}
+
+/// Calculates and compares the debt trajectory for both Price and SAC amortization systems.
+///
+/// This is the main entry point of the library. It takes the full loan configuration and
+/// returns a struct containing detailed results for both financing tables.
+///
+/// # Arguments
+///
+/// * `config` - A `LoanConfig` describing the loan and every optional feature applied to it.
+///
+/// # Errors
+///
+/// Returns an error if the `total_months` is zero or the interest rate is at or below -100%.
+pub fn calculate(config: &LoanConfig) -> Result<DebtTrajectoryResult, FinancingError> {
     // Convert annual percentage to monthly decimal
-    let monthly_interest_rate = normalize_annual_interest_rate(input.interest_per_year);
-    let financed_amount = clean_down_payment(input.total_amount, input.down_payment_percent);
+    calculate_with_context(config, RateContext::new(config.interest_per_year)?)
+}
 
-    let price_table = calculate_price_table(
-        financed_amount,
-        monthly_interest_rate,
-        input.total_months,
-    )?;
+/// Calculates the debt trajectory for a rate that is already monthly-effective.
+///
+/// Unlike `calculate_debt_trajectory`, the rate is not normalized from an annual
+/// percentage, and no down payment is deducted from `total_amount`.
+///
+/// # Arguments
+///
+/// * `total_amount` - The financed amount.
+/// * `monthly_rate` - The effective monthly interest rate, see `MonthlyRate`.
+/// * `total_months` - The total number of payments.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero.
+pub fn calculate_debt_trajectory_with_monthly_rate(
+    total_amount: Decimal,
+    monthly_rate: MonthlyRate,
+    total_months: u32,
+) -> Result<DebtTrajectoryResult, FinancingError> {
+    let context = RateContext::from_monthly_rate(monthly_rate);
+    let config = LoanConfig {
+        total_amount,
+        interest_per_year: context.interest_per_year,
+        total_months,
+        ..Default::default()
+    };
+
+    calculate_with_context(&config, context)
+}
+
+fn calculate_with_context(config: &LoanConfig, context: RateContext) -> Result<DebtTrajectoryResult, FinancingError> {
+    if config.finance_insurance_upfront && !(config.insurance_upfront && config.mip_rate.is_some()) {
+        return Err(FinancingError::InvalidArgument(
+            "Financing the insurance requires an MIP rate paid upfront.".to_string(),
+        ));
+    }
+    let mut result = trajectory_with_context(config, context.clone(), dec!(0))?;
+    if config.finance_insurance_upfront {
+        let price_premium = result.price_table.upfront_insurance;
+        let sac_premium = result.sac_table.upfront_insurance;
+        result.price_table = PriceTableResult {
+            upfront_insurance: price_premium,
+            ..trajectory_with_context(config, context.clone(), price_premium)?.price_table
+        };
+        result.sac_table = SacTableResult {
+            upfront_insurance: sac_premium,
+            ..trajectory_with_context(config, context, sac_premium)?.sac_table
+        };
+    }
+
+    Ok(result)
+}
+
+/// `calculate_with_context` with `financed_premium` added to the principal after the down
+/// payment and the upfront subsidy.
+fn trajectory_with_context(
+    config: &LoanConfig,
+    mut context: RateContext,
+    financed_premium: Decimal,
+) -> Result<DebtTrajectoryResult, FinancingError> {
+    let mut financed_amount = clean_down_payment(config.total_amount, config.down_payment_percent);
+
+    let subsidy = (config.subsidy_upfront.is_some() || config.subsidy_monthly_rate_reduction.is_some()).then(|| {
+        let upfront = config.subsidy_upfront.unwrap_or_default().min(financed_amount);
+        let monthly_rate_reduction = config.subsidy_monthly_rate_reduction.unwrap_or_default();
+        financed_amount -= upfront;
+        context = RateContext {
+            monthly_interest_rate: (context.monthly_interest_rate - monthly_rate_reduction).max(dec!(0)),
+            growth_factors: Vec::new(),
+            ..context
+        };
+        SubsidyReport {
+            upfront,
+            monthly_rate_reduction,
+            effective_monthly_rate: context.monthly_interest_rate,
+        }
+    });
+    let loan_amount = financed_amount;
+    financed_amount += financed_premium;
+
+    let mut price_table = context.price_table(financed_amount, config.total_months)?;
+    let mut sac_table = context.sac_table(financed_amount, config.total_months)?;
+    let mut adjusted = false;
+    if config.annuity_due && config.day_count == DayCount::Actual365 {
+        return Err(FinancingError::InvalidArgument(
+            "Annuity-due installments cannot be combined with the Actual365 day count.".to_string(),
+        ));
+    }
+    if config.annuity_due {
+        let fixed_payment = annuity_due_payment(financed_amount, context.monthly_interest_rate, config.total_months);
+        price_table.amortization_curve =
+            annuity_due_curve(financed_amount, fixed_payment, context.monthly_interest_rate, config.total_months);
+        price_table.fixed_payment = fixed_payment.round_dp(2);
+        adjusted = true;
+    }
+    if config.first_payment_month.is_some_and(|first_payment_month| !(1..=12).contains(&first_payment_month.month)) {
+        return Err(FinancingError::InvalidArgument("First payment month must be between 1 and 12.".to_string()));
+    }
+    let seasonal_start = config.first_payment_month.unwrap_or(YearMonth { year: 2000, month: 1 });
+    if !config.seasonal_weights.is_empty() {
+        validate_seasonal_weights(&config.seasonal_weights, seasonal_start, config.total_months)?;
+    }
+    if config.seasonal_weights.iter().any(|weight| *weight != dec!(1)) {
+        if config.annuity_due || config.day_count == DayCount::Actual365 {
+            return Err(FinancingError::InvalidArgument(
+                "Seasonal weights cannot be combined with annuity-due or Actual365 schedules.".to_string(),
+            ));
+        }
+        let (fixed_payment, curve) = seasonal_price_curve(
+            financed_amount,
+            context.monthly_interest_rate,
+            config.total_months,
+            &config.seasonal_weights,
+            seasonal_start,
+        );
+        price_table.fixed_payment = fixed_payment.round_dp(2);
+        price_table.amortization_curve = curve;
+        adjusted = true;
+    }
+    if config.day_count == DayCount::Actual365 {
+        let Some(first_payment_month) = config.first_payment_month else {
+            return Err(FinancingError::InvalidArgument(
+                "Actual365 day count requires the first payment month.".to_string(),
+            ));
+        };
+        let annual_rate = config.interest_per_year / dec!(100);
+        let period_days: Vec<u32> =
+            (0..config.total_months).map(|month| first_payment_month.add_months(month).days_in_month()).collect();
+        let (fixed_payment, curve) =
+            actual_day_count_curve(financed_amount, annual_rate, &period_days, AmortizationSystem::Price);
+        price_table.fixed_payment = fixed_payment.round_dp(2);
+        price_table.amortization_curve = curve;
+        sac_table.amortization_curve =
+            actual_day_count_curve(financed_amount, annual_rate, &period_days, AmortizationSystem::Sac).1;
+        adjusted = true;
+    }
+    if config.final_stub_fraction.is_some_and(|fraction| fraction <= dec!(0) || fraction >= dec!(1)) {
+        return Err(FinancingError::InvalidArgument("Final stub fraction must be between 0 and 1.".to_string()));
+    }
 
-    let sac_table = calculate_sac_table(
+    let accrued_interest = signing_accrued_interest(
         financed_amount,
-        monthly_interest_rate,
-        input.total_months,
-    )?;
+        context.monthly_interest_rate,
+        config.signing_to_first_payment_months,
+    );
+    let monthly_extra = config.monthly_extra.filter(|extra| *extra > dec!(0)).unwrap_or_default();
+    let mut rounding_extra = dec!(0);
+    if let Some(granularity) = config.round_installment_to.filter(|granularity| *granularity > dec!(0)) {
+        if !config.seasonal_weights.is_empty() {
+            return Err(FinancingError::InvalidArgument(
+                "Installment rounding cannot be combined with seasonal weights.".to_string(),
+            ));
+        }
+        let fixed_payment = price_table.amortization_curve[0].installment();
+        let rounded_payment = (fixed_payment / granularity).ceil() * granularity;
+        rounding_extra = rounded_payment - fixed_payment;
+        price_table.fixed_payment = rounded_payment.round_dp(2);
+    }
+    let mut sac_minimum_extra = dec!(0);
+    if let Some(minimum) = config.min_first_installment {
+        let first_month =
+            |curve: &[MonthPayment]| (curve[0].installment(), curve[0].opening_balance() + curve[0].current_interest);
+        let (price_first, price_payoff) = first_month(&price_table.amortization_curve);
+        let (sac_first, sac_payoff) = first_month(&sac_table.amortization_curve);
+        if minimum > price_payoff || minimum > sac_payoff {
+            return Err(FinancingError::InvalidArgument(
+                "Minimum first installment exceeds the payoff amount of the first month.".to_string(),
+            ));
+        }
+        let price_payment = price_first + rounding_extra;
+        if price_payment < minimum {
+            rounding_extra += minimum - price_payment;
+            price_table.fixed_payment = minimum.round_dp(2);
+        }
+        sac_minimum_extra = (minimum - sac_first).max(dec!(0));
+    }
+    for (table_curve, interest_saved, system, extra) in [
+        (
+            &mut price_table.amortization_curve,
+            &mut price_table.interest_saved,
+            AmortizationSystem::Price,
+            monthly_extra + rounding_extra,
+        ),
+        (
+            &mut sac_table.amortization_curve,
+            &mut sac_table.interest_saved,
+            AmortizationSystem::Sac,
+            monthly_extra + sac_minimum_extra,
+        ),
+    ] {
+        if extra > dec!(0) {
+            let with_extra = extra_amortization_curve(table_curve, extra, system);
+            *interest_saved = (total_interest(table_curve) - total_interest(&with_extra)).round_dp(2);
+            *table_curve = with_extra;
+            adjusted = true;
+        }
+    }
+    if !config.prepayments.is_empty() {
+        for (table_curve, interest_saved, system) in [
+            (&mut price_table.amortization_curve, &mut price_table.interest_saved, AmortizationSystem::Price),
+            (&mut sac_table.amortization_curve, &mut sac_table.interest_saved, AmortizationSystem::Sac),
+        ] {
+            let plan = apply_prepayment_schedule(table_curve, &config.prepayments, config.prepayment_strategy, system)?;
+            *interest_saved += plan.interest_saved;
+            *table_curve = plan.amortization_curve;
+        }
+        adjusted = true;
+    }
+    if let Some(fraction) = config.final_stub_fraction {
+        for curve in [&mut price_table.amortization_curve, &mut sac_table.amortization_curve] {
+            if curve.len() == config.total_months as usize {
+                apply_final_stub(curve, fraction);
+                adjusted = true;
+            }
+        }
+    }
+    for curve in [&mut price_table.amortization_curve, &mut sac_table.amortization_curve] {
+        adjusted |= settle_residual_balance(curve, financed_amount, config.settlement_threshold);
+    }
+    price_table.settled_at_month = early_settlement_month(&price_table.amortization_curve, config.total_months);
+    sac_table.settled_at_month = early_settlement_month(&sac_table.amortization_curve, config.total_months);
+    if config.grace_months > 0 {
+        for curve in [&mut price_table.amortization_curve, &mut sac_table.amortization_curve] {
+            prepend_grace_period(
+                curve,
+                financed_amount,
+                context.monthly_interest_rate,
+                config.grace_months,
+                config.grace_transition,
+            );
+        }
+        for settled_at_month in [&mut price_table.settled_at_month, &mut sac_table.settled_at_month] {
+            *settled_at_month = settled_at_month.map(|month| month + config.grace_months);
+        }
+        adjusted = true;
+    }
+    if accrued_interest > dec!(0) {
+        let spread_months = config.signing_to_first_payment_months.min(config.total_months);
+        spread_accrued_interest(&mut price_table.amortization_curve, accrued_interest, spread_months);
+        spread_accrued_interest(&mut sac_table.amortization_curve, accrued_interest, spread_months);
+        adjusted = true;
+    }
+    if !config.monthly_fees.is_empty() {
+        apply_fee_schedule(&mut price_table.amortization_curve, &config.monthly_fees);
+        apply_fee_schedule(&mut sac_table.amortization_curve, &config.monthly_fees);
+        adjusted = true;
+    }
+    if let Some(mip_rate) = config.mip_rate {
+        apply_mip(&mut price_table.amortization_curve, mip_rate);
+        apply_mip(&mut sac_table.amortization_curve, mip_rate);
+        if config.insurance_upfront {
+            price_table.upfront_insurance = take_insurance(&mut price_table.amortization_curve);
+            sac_table.upfront_insurance = take_insurance(&mut sac_table.amortization_curve);
+        }
+        adjusted = true;
+    }
+    if let Some(employer_subsidy) = config.employer_subsidy_monthly {
+        if employer_subsidy < dec!(0) {
+            return Err(FinancingError::InvalidArgument("Employer subsidy cannot be negative.".to_string()));
+        }
+        for payment in price_table.amortization_curve.iter_mut().chain(sac_table.amortization_curve.iter_mut()) {
+            payment.employer_subsidy = employer_subsidy.min(payment.installment());
+        }
+    }
+    if adjusted {
+        price_table.recompute_totals();
+        sac_table.recompute_totals();
+    }
 
-    Ok(DebtTrajectoryResult { financed_amount, price_table, sac_table })
+    Ok(DebtTrajectoryResult {
+        financed_amount: loan_amount,
+        monthly_interest_rate: context.monthly_interest_rate,
+        accrued_interest,
+        subsidy,
+        grace_transition: (config.grace_months > 0).then_some(config.grace_transition),
+        price_table,
+        sac_table,
+    })
 }
 
 /// Calculates the financing trajectory using the Price table (fixed payments).
@@ -191,26 +1264,43 @@ pub fn calculate_debt_trajectory(input: DebtCalculationInput) -> Result<DebtTraj
 /// # Arguments
 ///
 /// * `total_amount` - The principal loan amount.
-/// * `monthly_interest_rate` - The effective monthly interest rate as a decimal (not percentage).
+/// * `monthly_interest_rate` - The effective monthly interest rate, see `MonthlyRate`.
 /// * `total_months` - The total number of payments.
 ///
 /// # Errors
 ///
-/// Returns an error if `total_months` is zero.
+/// Returns an error if `total_months` is zero. The error wraps a `FinancingError`.
 pub fn calculate_price_table(
     total_amount: Decimal,
-    monthly_interest_rate: Decimal,
+    monthly_interest_rate: MonthlyRate,
     total_months: u32,
 ) -> Result<PriceTableResult, anyhow::Error> {
+    Ok(build_price_table(total_amount, monthly_interest_rate, total_months)?)
+}
+
+/// `calculate_price_table` with the typed error used by the rest of the crate.
+fn build_price_table(
+    total_amount: Decimal,
+    monthly_interest_rate: MonthlyRate,
+    total_months: u32,
+) -> Result<PriceTableResult, FinancingError> {
     if total_months == 0 {
-        return Err(anyhow::anyhow!("Total months cannot be zero."));
+        return Err(FinancingError::ZeroMonths);
     }
 
-    // Price table formula: PMT = P * [i(1 + i)^n] / [(1 + i)^n – 1]
-    let i_plus_1_pow_n = (dec!(1) + monthly_interest_rate).powu(total_months.into());
-    let fixed_payment =
-        total_amount * (monthly_interest_rate * i_plus_1_pow_n) / (i_plus_1_pow_n - dec!(1));
+    let monthly_interest_rate = monthly_interest_rate.value();
+    let fixed_payment = price_fixed_payment(total_amount, monthly_interest_rate, total_months);
+
+    Ok(price_table_with_payment(total_amount, monthly_interest_rate, total_months, fixed_payment))
+}
 
+/// Runs the Price schedule for an already computed fixed payment.
+fn price_table_with_payment(
+    total_amount: Decimal,
+    monthly_interest_rate: Decimal,
+    total_months: u32,
+    fixed_payment: Decimal,
+) -> PriceTableResult {
     let mut current_balance = total_amount;
     let mut total_paid = dec!(0);
     let mut amortization_curve = Vec::new();
@@ -224,16 +1314,75 @@ pub fn calculate_price_table(
             MonthPayment {
                 new_balance: current_balance.max(dec!(0)),
                 current_amortization: amortization,
-                current_interest: interest_payment
+                current_interest: interest_payment,
+                accrued_interest: dec!(0),
+                admin_fee: dec!(0),
+                insurance: dec!(0),
+                principal_paid_to_date: total_amount - current_balance,
+                present_value: None,
+                applied_rate: None,
+                employer_subsidy: dec!(0),
             }
         );
+        #[cfg(feature = "tracing")]
+        trace_month(0, &amortization_curve);
     }
 
-    Ok(PriceTableResult {
+    #[cfg(feature = "debug_invariants")]
+    assert_schedule_invariants(total_amount, monthly_interest_rate, total_paid, &amortization_curve);
+
+    PriceTableResult {
         fixed_payment: fixed_payment.round_dp(2),
         total_paid: total_paid.round_dp(2),
+        total_interest: total_interest(&amortization_curve).round_dp(2),
         amortization_curve,
-    })
+        settled_at_month: None,
+        interest_saved: dec!(0),
+        upfront_insurance: dec!(0),
+    }
+}
+
+/// Emits a `trace`-level event with the figures of the month just pushed to the
+/// curve, when the `tracing` feature is enabled. `months_before` offsets the month
+/// number for tails that start partway through the schedule.
+#[cfg(feature = "tracing")]
+fn trace_month(months_before: u32, amortization_curve: &[MonthPayment]) {
+    if let Some(payment) = amortization_curve.last() {
+        tracing::trace!(
+            month = months_before + amortization_curve.len() as u32,
+            opening_balance = %payment.opening_balance(),
+            interest = %payment.current_interest,
+            amortization = %payment.current_amortization,
+            closing_balance = %payment.new_balance,
+            "schedule month"
+        );
+    }
+}
+
+/// Asserts the invariants every schedule must satisfy, when the `debug_invariants`
+/// feature is enabled: the principal is fully amortized, `total_paid` matches the summed
+/// curve, and no negative balance, amortization or (for non-negative rates) interest
+/// appears.
+#[cfg(feature = "debug_invariants")]
+fn assert_schedule_invariants(
+    total_amount: Decimal,
+    monthly_interest_rate: Decimal,
+    total_paid: Decimal,
+    amortization_curve: &[MonthPayment],
+) {
+    let cent = dec!(0.01);
+    let amortized: Decimal = amortization_curve.iter().map(|p| p.current_amortization).sum();
+    let summed: Decimal = amortization_curve.iter().map(MonthPayment::installment).sum();
+
+    assert!((amortized - total_amount).abs() <= cent, "principal not fully amortized: {} of {}", amortized, total_amount);
+    assert!((summed - total_paid).abs() <= cent, "total paid {} does not match the curve sum {}", total_paid, summed);
+    for (month, payment) in amortization_curve.iter().enumerate() {
+        assert!(payment.new_balance >= dec!(0), "negative balance in month {}", month + 1);
+        assert!(payment.current_amortization >= dec!(0), "negative amortization in month {}", month + 1);
+        if monthly_interest_rate >= dec!(0) {
+            assert!(payment.current_interest >= dec!(0), "negative interest in month {}", month + 1);
+        }
+    }
 }
 
 /// Calculates the financing trajectory using the SAC (Constant Amortization System).
@@ -244,21 +1393,31 @@ pub fn calculate_price_table(
 /// # Arguments
 ///
 /// * `total_amount` - The principal loan amount.
-/// * `monthly_interest_rate` - The effective monthly interest rate as a decimal (not percentage).
+/// * `monthly_interest_rate` - The effective monthly interest rate, see `MonthlyRate`.
 /// * `total_months` - The total number of payments.
 ///
 /// # Errors
 ///
-/// Returns an error if `total_months` is zero.
+/// Returns an error if `total_months` is zero. The error wraps a `FinancingError`.
 pub fn calculate_sac_table(
     total_amount: Decimal,
-    monthly_interest_rate: Decimal,
+    monthly_interest_rate: MonthlyRate,
     total_months: u32,
 ) -> Result<SacTableResult, anyhow::Error> {
-    if total_months == 0 {
-        return Err(anyhow::anyhow!("Total months cannot be zero."));
-    }
+    Ok(build_sac_table(total_amount, monthly_interest_rate, total_months)?)
+}
 
+/// `calculate_sac_table` with the typed error used by the rest of the crate.
+fn build_sac_table(
+    total_amount: Decimal,
+    monthly_interest_rate: MonthlyRate,
+    total_months: u32,
+) -> Result<SacTableResult, FinancingError> {
+    if total_months == 0 {
+        return Err(FinancingError::ZeroMonths);
+    }
+
+    let monthly_interest_rate = monthly_interest_rate.value();
     let fixed_amortization = total_amount / Decimal::from(total_months);
     let mut current_balance = total_amount;
     let mut first_payment: Option<Decimal> = None;
@@ -267,8 +1426,11 @@ pub fn calculate_sac_table(
     let mut amortization_curve = Vec::new();
 
     for month in 0..total_months {
+        // Never charge interest on a balance driven below zero by inexact division.
+        current_balance = current_balance.max(dec!(0));
         let interest_payment = current_balance * monthly_interest_rate;
-        let current_payment = fixed_amortization + interest_payment;
+        let amortization = sac_month_amortization(current_balance, fixed_amortization, month == total_months - 1);
+        let current_payment = amortization + interest_payment;
 
         if month == 0 {
             first_payment = Some(current_payment);
@@ -277,121 +1439,6197 @@ pub fn calculate_sac_table(
             last_payment = Some(current_payment);
         }
 
-        current_balance -= fixed_amortization;
+        current_balance -= amortization;
         total_paid += current_payment;
         amortization_curve.push(
             MonthPayment {
                 new_balance: current_balance.max(dec!(0)),
-                current_amortization: fixed_amortization,
-                current_interest: interest_payment
+                current_amortization: amortization,
+                current_interest: interest_payment,
+                accrued_interest: dec!(0),
+                admin_fee: dec!(0),
+                insurance: dec!(0),
+                principal_paid_to_date: total_amount - current_balance,
+                present_value: None,
+                applied_rate: None,
+                employer_subsidy: dec!(0),
             }
         );
+        #[cfg(feature = "tracing")]
+        trace_month(0, &amortization_curve);
     }
 
+    #[cfg(feature = "debug_invariants")]
+    assert_schedule_invariants(total_amount, monthly_interest_rate, total_paid, &amortization_curve);
+
     Ok(SacTableResult {
         fixed_amortization: fixed_amortization.round_dp(2),
         first_payment: first_payment.unwrap_or_default().round_dp(2),
         last_payment: last_payment.unwrap_or_default().round_dp(2),
         total_paid: total_paid.round_dp(2),
+        total_interest: total_interest(&amortization_curve).round_dp(2),
         amortization_curve,
+        settled_at_month: None,
+        interest_saved: dec!(0),
+        upfront_insurance: dec!(0),
     })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rust_decimal_macros::dec;
+/// Calculates the fixed monthly payment of the Price table.
+///
+/// PMT = P * [i(1 + i)^n] / [(1 + i)^n – 1]
+///
+/// For a single payment the formula reduces to `P(1 + i)`, which is returned directly to
+/// avoid losing precision in `(1 + i) - 1`. A zero rate reduces to `P / n`.
+fn price_fixed_payment(total_amount: Decimal, monthly_interest_rate: Decimal, total_months: u32) -> Decimal {
+    if monthly_interest_rate.is_zero() {
+        return total_amount / Decimal::from(total_months);
+    }
+    if total_months == 1 {
+        return total_amount * (dec!(1) + monthly_interest_rate);
+    }
 
-    #[test]
-    fn test_calculate_debt_trajectory_happy_path() {
-        let input = DebtCalculationInput {
-            total_amount: dec!(12000),
-            interest_per_year: dec!(12),
-            down_payment_percent: dec!(0),
-            total_months: 12,
-        };
+    let i_plus_1_pow_n = (dec!(1) + monthly_interest_rate).powu(total_months.into());
 
-        let result = calculate_debt_trajectory(input).unwrap();
+    price_payment_from_growth(total_amount, monthly_interest_rate, i_plus_1_pow_n)
+}
 
-        // Assertions for SAC table
-        assert_eq!(result.sac_table.fixed_amortization.round_dp(2), dec!(1000.00));
-        assert_eq!(result.sac_table.first_payment.round_dp(2), dec!(1113.87));
-        assert_eq!(result.sac_table.last_payment.round_dp(2), dec!(1009.49));
-        assert_eq!(result.sac_table.total_paid.round_dp(2), dec!(12740.13));
+/// Evaluates the Price formula given the growth factor `(1 + i)^n`.
+fn price_payment_from_growth(total_amount: Decimal, monthly_interest_rate: Decimal, i_plus_1_pow_n: Decimal) -> Decimal {
+    total_amount * (monthly_interest_rate * i_plus_1_pow_n) / (i_plus_1_pow_n - dec!(1))
+}
 
-        // Assertions for Price table
-        assert_eq!(result.price_table.fixed_payment.round_dp(2), dec!(1062.74));
-        assert_eq!(result.price_table.total_paid.round_dp(2), dec!(12752.94));
+/// Returns the SAC amortization for a month with the given opening balance.
+///
+/// In the final month, if the remaining balance differs from the fixed amortization by
+/// more than a cent, the whole remaining balance is amortized so the loan ends at zero.
+fn sac_month_amortization(opening_balance: Decimal, fixed_amortization: Decimal, is_last_month: bool) -> Decimal {
+    if is_last_month && (opening_balance - fixed_amortization).abs() > dec!(0.01) {
+        return opening_balance;
     }
 
-    #[test]
-    fn test_calculate_debt_trajectory_with_down_payment10() {
-        let input = DebtCalculationInput {
-            total_amount: dec!(12000),
-            interest_per_year: dec!(12),
-            down_payment_percent: dec!(10.0),
-            total_months: 12,
-        };
+    fixed_amortization
+}
 
-        let result = calculate_debt_trajectory(input).unwrap();
+/// Calculates only the last `n` months of a SAC schedule.
+///
+/// Instead of iterating over the whole term, the balance at the start of the tail is
+/// obtained directly as `P - k * A`, where `A` is the fixed amortization and `k` the
+/// number of months skipped. The returned rows match the corresponding slice of
+/// `calculate_sac_table(...).amortization_curve`.
+///
+/// # Arguments
+///
+/// * `total_amount` - The principal loan amount.
+/// * `monthly_interest_rate` - The effective monthly interest rate as a decimal (not percentage).
+/// * `total_months` - The total number of payments.
+/// * `n` - How many months from the end of the schedule to return (clamped to `total_months`).
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero.
+pub fn sac_tail(
+    total_amount: Decimal,
+    monthly_interest_rate: Decimal,
+    total_months: u32,
+    n: u32,
+) -> Result<Vec<MonthPayment>, FinancingError> {
+    if total_months == 0 {
+        return Err(FinancingError::ZeroMonths);
+    }
 
-        assert_eq!(result.financed_amount.round_dp(2), dec!(10800.00));
+    let n = n.min(total_months);
+    let fixed_amortization = total_amount / Decimal::from(total_months);
+    let skipped = total_months - n;
+    let mut current_balance = total_amount - fixed_amortization * Decimal::from(skipped);
+    let mut amortization_curve = Vec::with_capacity(n as usize);
 
-        // Assertions for SAC table
-        assert_eq!(result.sac_table.fixed_amortization.round_dp(2), dec!(900.00));
-        assert_eq!(result.sac_table.first_payment.round_dp(2), dec!(1002.48));
-        assert_eq!(result.sac_table.last_payment.round_dp(2), dec!(908.54));
-        assert_eq!(result.sac_table.total_paid.round_dp(2), dec!(11466.11));
+    for month in skipped..total_months {
+        current_balance = current_balance.max(dec!(0));
+        let interest_payment = current_balance * monthly_interest_rate;
+        let amortization = sac_month_amortization(current_balance, fixed_amortization, month == total_months - 1);
+        current_balance -= amortization;
+        amortization_curve.push(
+            MonthPayment {
+                new_balance: current_balance.max(dec!(0)),
+                current_amortization: amortization,
+                current_interest: interest_payment,
+                accrued_interest: dec!(0),
+                admin_fee: dec!(0),
+                insurance: dec!(0),
+                principal_paid_to_date: total_amount - current_balance,
+                present_value: None,
+                applied_rate: None,
+                employer_subsidy: dec!(0),
+            }
+        );
+        #[cfg(feature = "tracing")]
+        trace_month(skipped, &amortization_curve);
+    }
 
-        // Assertions for Price table
-        assert_eq!(result.price_table.fixed_payment.round_dp(2), dec!(956.47));
-        assert_eq!(result.price_table.total_paid.round_dp(2), dec!(11477.64));
+    Ok(amortization_curve)
+}
+
+/// Calculates only the last `n` months of a Price schedule.
+///
+/// The balance at the start of the tail is obtained with the closed form
+/// `B_k = P(1 + i)^k - PMT[(1 + i)^k - 1] / i`, or `P - k * PMT` when the rate is zero,
+/// so only the requested months are iterated. The returned rows match the corresponding slice of
+/// `calculate_price_table(...).amortization_curve`.
+///
+/// # Arguments
+///
+/// * `total_amount` - The principal loan amount.
+/// * `monthly_interest_rate` - The effective monthly interest rate as a decimal (not percentage).
+/// * `total_months` - The total number of payments.
+/// * `n` - How many months from the end of the schedule to return (clamped to `total_months`).
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero.
+pub fn price_tail(
+    total_amount: Decimal,
+    monthly_interest_rate: Decimal,
+    total_months: u32,
+    n: u32,
+) -> Result<Vec<MonthPayment>, FinancingError> {
+    if total_months == 0 {
+        return Err(FinancingError::ZeroMonths);
     }
 
-    #[test]
-    fn test_calculate_debt_trajectory_with_down_payment40() {
-        let input = DebtCalculationInput {
-            total_amount: dec!(12000),
-            interest_per_year: dec!(12),
-            down_payment_percent: dec!(40),
-            total_months: 12,
-        };
+    let n = n.min(total_months);
+    let fixed_payment = price_fixed_payment(total_amount, monthly_interest_rate, total_months);
+    let skipped = total_months - n;
+    let mut current_balance = if monthly_interest_rate.is_zero() {
+        total_amount - fixed_payment * Decimal::from(skipped)
+    } else {
+        let growth = (dec!(1) + monthly_interest_rate).powu(skipped.into());
+        total_amount * growth - fixed_payment * (growth - dec!(1)) / monthly_interest_rate
+    };
+    let mut amortization_curve = Vec::with_capacity(n as usize);
 
-        let result = calculate_debt_trajectory(input).unwrap();
+    for _ in 0..n {
+        let interest_payment = current_balance * monthly_interest_rate;
+        let amortization = fixed_payment - interest_payment;
+        current_balance -= amortization;
+        amortization_curve.push(
+            MonthPayment {
+                new_balance: current_balance.max(dec!(0)),
+                current_amortization: amortization,
+                current_interest: interest_payment,
+                accrued_interest: dec!(0),
+                admin_fee: dec!(0),
+                insurance: dec!(0),
+                principal_paid_to_date: total_amount - current_balance,
+                present_value: None,
+                applied_rate: None,
+                employer_subsidy: dec!(0),
+            }
+        );
+        #[cfg(feature = "tracing")]
+        trace_month(skipped, &amortization_curve);
+    }
 
-        assert_eq!(result.financed_amount.round_dp(2), dec!(7200.00));
+    Ok(amortization_curve)
+}
 
-        // Assertions for SAC table
-        assert_eq!(result.sac_table.fixed_amortization.round_dp(2), dec!(600.00));
-        assert_eq!(result.sac_table.first_payment.round_dp(2), dec!(668.32));
-        assert_eq!(result.sac_table.last_payment.round_dp(2), dec!(605.69));
-        assert_eq!(result.sac_table.total_paid.round_dp(2), dec!(7644.08));
+/// Estimates the total insurance paid over the life of a Price loan.
+///
+/// MIP (death and disability insurance) is charged monthly on the opening balance and
+/// DFI (property damage insurance) monthly on the property value. The sum of opening
+/// balances uses the closed form `(P - PMT / i)[(1 + i)^n - 1] / i + n PMT / i`, so no
+/// schedule is built.
+///
+/// # Arguments
+///
+/// * `total_amount` - The principal loan amount.
+/// * `monthly_rate` - The effective monthly interest rate as a decimal (not percentage).
+/// * `total_months` - The total number of payments.
+/// * `mip_rate` - The monthly MIP rate as a decimal, applied to the balance.
+/// * `dfi_rate` - The monthly DFI rate as a decimal, applied to the property value.
+/// * `property_value` - The appraised value of the property.
+pub fn total_insurance_cost(
+    total_amount: Decimal,
+    monthly_rate: Decimal,
+    total_months: u32,
+    mip_rate: Decimal,
+    dfi_rate: Decimal,
+    property_value: Decimal,
+) -> Decimal {
+    if total_months == 0 {
+        return dec!(0);
+    }
 
-        // Assertions for Price table
-        assert_eq!(result.price_table.fixed_payment.round_dp(2), dec!(637.65));
-        assert_eq!(result.price_table.total_paid.round_dp(2), dec!(7651.76));
+    let months = Decimal::from(total_months);
+    let balance_sum = if monthly_rate.is_zero() {
+        total_amount * (months + dec!(1)) / dec!(2)
+    } else {
+        let fixed_payment = price_fixed_payment(total_amount, monthly_rate, total_months);
+        let growth = (dec!(1) + monthly_rate).powu(total_months.into());
+        (total_amount - fixed_payment / monthly_rate) * (growth - dec!(1)) / monthly_rate
+            + months * fixed_payment / monthly_rate
+    };
+
+    (balance_sum * mip_rate + months * property_value * dfi_rate).round_dp(2)
+}
+
+/// A monthly interest rate as a decimal (0.008 for 0.8% a month).
+///
+/// The table calculations take this type instead of a bare `Decimal`, so an annual
+/// percentage such as `10.5` cannot be passed where a monthly decimal is expected.
+/// `From<Decimal>` wraps an already-monthly decimal unchecked, easing migration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MonthlyRate(Decimal);
+
+impl MonthlyRate {
+    /// Creates a monthly rate from a monthly decimal.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FinancingError::RateOutOfRange` if the rate is at or below -100%.
+    pub fn new(monthly_rate: Decimal) -> Result<Self, FinancingError> {
+        if monthly_rate <= dec!(-1) {
+            return Err(FinancingError::RateOutOfRange(monthly_rate * dec!(100)));
+        }
+
+        Ok(MonthlyRate(monthly_rate))
     }
 
-    #[test]
-    fn test_normalize_annual_interest_rate() {
-        // 12% per year should be a bit less than 1% per month when compounded.
-        let annual_rate = dec!(12);
-        let monthly_rate = normalize_annual_interest_rate(annual_rate);
-        // Effective monthly rate for 12% annual is approx 0.9488%
-        // (1.12)^(1/12) - 1 = 0.009488...
-        // Let's check for a value in that range.
-        assert!(monthly_rate > dec!(0.0094) && monthly_rate < dec!(0.0095));
+    /// Creates the monthly rate equivalent to an annual percentage (e.g., 10.5 for 10.5%).
+    ///
+    /// # Errors
+    ///
+    /// Returns `FinancingError::RateOutOfRange` if the rate is at or below -100%.
+    pub fn from_annual_percent(interest_per_year: Decimal) -> Result<Self, FinancingError> {
+        normalize_rate(interest_per_year, 12).map(MonthlyRate)
     }
 
-    #[test]
-    fn test_zero_months_error() {
-        let input = DebtCalculationInput {
-            total_amount: dec!(100000),
-            interest_per_year: dec!(10),
-            down_payment_percent: dec!(0),
-            total_months: 0,
+    /// Returns the rate as a monthly decimal.
+    pub fn value(&self) -> Decimal {
+        self.0
+    }
+}
+
+impl From<Decimal> for MonthlyRate {
+    fn from(monthly_rate: Decimal) -> Self {
+        MonthlyRate(monthly_rate)
+    }
+}
+
+impl From<MonthlyRate> for Decimal {
+    fn from(rate: MonthlyRate) -> Self {
+        rate.0
+    }
+}
+
+/// A reusable calculation context for scenarios sharing the same annual rate.
+///
+/// Normalizing the annual rate involves a fractional power (`powd`), and the Price
+/// installment an integer power `(1 + i)^n`; both become measurable when thousands of
+/// scenarios are evaluated. `RateContext` performs the normalization once and, after
+/// `with_terms_up_to`, caches the growth factor of every term, so the Price installment
+/// of any amount costs a single multiplication and division.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateContext {
+    interest_per_year: Decimal,
+    monthly_interest_rate: Decimal,
+    #[serde(skip)]
+    growth_factors: Vec<Decimal>,
+}
+
+impl RateContext {
+    /// Creates a context by normalizing the annual percentage rate once.
+    ///
+    /// # Errors
+    ///
+    /// Returns `FinancingError::RateOutOfRange` if the rate is at or below -100%.
+    pub fn new(interest_per_year: Decimal) -> Result<Self, FinancingError> {
+        Ok(RateContext {
+            interest_per_year,
+            monthly_interest_rate: normalize_rate(interest_per_year, 12)?,
+            growth_factors: Vec::new(),
+        })
+    }
+
+    /// Creates a context from a monthly rate, deriving the equivalent annual percentage.
+    pub fn from_monthly_rate(monthly_rate: MonthlyRate) -> Self {
+        let monthly_interest_rate = monthly_rate.value();
+        RateContext {
+            interest_per_year: ((dec!(1) + monthly_interest_rate).powu(12) - dec!(1)) * dec!(100),
+            monthly_interest_rate,
+            growth_factors: Vec::new(),
+        }
+    }
+
+    /// Caches the growth factor `(1 + i)^n` for every term `n` up to `max_months`.
+    pub fn with_terms_up_to(mut self, max_months: u32) -> Self {
+        let base = dec!(1) + self.monthly_interest_rate;
+        self.growth_factors = (0..=max_months).map(|months| base.powu(months.into())).collect();
+        self
+    }
+
+    /// Returns the annual interest rate as a percentage (e.g., 10.5 for 10.5%).
+    pub fn interest_per_year(&self) -> Decimal {
+        self.interest_per_year
+    }
+
+    /// Returns the effective monthly interest rate as a decimal.
+    pub fn monthly_interest_rate(&self) -> Decimal {
+        self.monthly_interest_rate
+    }
+
+    /// Returns `(1 + i)^total_months`, from the cache when the term was precomputed.
+    pub fn growth_factor(&self, total_months: u32) -> Decimal {
+        match self.growth_factors.get(total_months as usize) {
+            Some(growth) => *growth,
+            None => (dec!(1) + self.monthly_interest_rate).powu(total_months.into()),
+        }
+    }
+
+    /// Returns the unrounded Price installment, equal to the one `calculate_price_table`
+    /// uses.
+    pub fn price_payment(&self, total_amount: Decimal, total_months: u32) -> Decimal {
+        if self.monthly_interest_rate.is_zero() || total_months <= 1 {
+            return price_fixed_payment(total_amount, self.monthly_interest_rate, total_months);
+        }
+
+        price_payment_from_growth(total_amount, self.monthly_interest_rate, self.growth_factor(total_months))
+    }
+
+    /// Calculates the Price table for the given amount and term using the cached rate and
+    /// growth factors.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `total_months` is zero.
+    pub fn price_table(&self, total_amount: Decimal, total_months: u32) -> Result<PriceTableResult, FinancingError> {
+        if total_months == 0 {
+            return Err(FinancingError::ZeroMonths);
+        }
+
+        let fixed_payment = self.price_payment(total_amount, total_months);
+        Ok(price_table_with_payment(total_amount, self.monthly_interest_rate, total_months, fixed_payment))
+    }
+
+    /// Calculates the SAC table for the given amount and term using the cached rate.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `total_months` is zero.
+    pub fn sac_table(&self, total_amount: Decimal, total_months: u32) -> Result<SacTableResult, FinancingError> {
+        build_sac_table(total_amount, self.monthly_interest_rate.into(), total_months)
+    }
+}
+
+/// Calculates the interest accrued between signing and the first installment.
+///
+/// Interest is charged on the full financed amount for each month of delay. Since it is
+/// not capitalized, it accrues as simple interest: `P * i * m`.
+pub fn signing_accrued_interest(
+    financed_amount: Decimal,
+    monthly_interest_rate: Decimal,
+    signing_to_first_payment_months: u32,
+) -> Decimal {
+    financed_amount * monthly_interest_rate * Decimal::from(signing_to_first_payment_months)
+}
+
+/// Settles the loan in the first month whose outstanding balance is at or below
+/// `threshold`, folding the residual into that month's amortization and dropping the
+/// months after it. Returns whether the curve was changed.
+fn settle_residual_balance(curve: &mut Vec<MonthPayment>, total_amount: Decimal, threshold: Decimal) -> bool {
+    let Some(month) = curve
+        .iter()
+        .position(|payment| total_amount - payment.principal_paid_to_date <= threshold)
+    else {
+        return false;
+    };
+
+    let months = curve.len();
+    let payment = &mut curve[month];
+    let residual = total_amount - payment.principal_paid_to_date;
+    let changed = !residual.is_zero() || month + 1 < months;
+    payment.current_amortization += residual;
+    payment.principal_paid_to_date = total_amount;
+    payment.new_balance = dec!(0);
+    curve.truncate(month + 1);
+
+    changed
+}
+
+/// Rebuilds a schedule with `extra` principal paid with every installment.
+///
+/// Each month keeps the scheduled amortization of `amortization_curve` (SAC) or its
+/// installment (Price), is charged the rate the curve realized in that month, and
+/// amortizes `extra` on top until the balance reaches zero. The month that pays off the
+/// loan amortizes only what remains. Building on the curve keeps the annuity-due,
+/// seasonal and day-count variants.
+fn extra_amortization_curve(
+    amortization_curve: &[MonthPayment],
+    extra: Decimal,
+    system: AmortizationSystem,
+) -> Vec<MonthPayment> {
+    let Some(first) = amortization_curve.first() else {
+        return Vec::new();
+    };
+    let total_amount = first.opening_balance();
+    let mut current_balance = total_amount;
+    let mut extra_curve = Vec::new();
+
+    for (index, original) in amortization_curve.iter().enumerate() {
+        let interest_payment = current_balance * original.realized_rate();
+        let scheduled = match system {
+            AmortizationSystem::Sac => original.current_amortization,
+            AmortizationSystem::Price => original.current_amortization + original.current_interest - interest_payment,
         };
-        let result = calculate_debt_trajectory(input);
-        assert!(result.is_err());
+        let amortization = if index == amortization_curve.len() - 1 {
+            current_balance
+        } else {
+            (scheduled + extra).min(current_balance)
+        };
+
+        current_balance -= amortization;
+        extra_curve.push(
+            MonthPayment {
+                new_balance: current_balance,
+                current_amortization: amortization,
+                current_interest: interest_payment,
+                accrued_interest: dec!(0),
+                admin_fee: dec!(0),
+                insurance: dec!(0),
+                principal_paid_to_date: total_amount - current_balance,
+                present_value: None,
+                applied_rate: None,
+                employer_subsidy: dec!(0),
+            }
+        );
+        #[cfg(feature = "tracing")]
+        trace_month(0, &extra_curve);
+        if current_balance <= dec!(0) {
+            break;
+        }
+    }
+
+    extra_curve
+}
+
+/// Returns the settlement month when the curve ends before the contracted term.
+fn early_settlement_month(curve: &[MonthPayment], total_months: u32) -> Option<u32> {
+    let months = curve.len() as u32;
+    (months < total_months).then_some(months)
+}
+
+/// Inserts `grace_months` interest-only installments (carência) before the curve: the
+/// balance stays at `total_amount` and each of them pays `total_amount * rate` rounded
+/// to cents. The rounding residual of all grace months is settled as `transition` says.
+fn prepend_grace_period(
+    curve: &mut Vec<MonthPayment>,
+    total_amount: Decimal,
+    monthly_interest_rate: Decimal,
+    grace_months: u32,
+    transition: GraceTransition,
+) {
+    let grace_interest = total_amount * monthly_interest_rate;
+    let residual = (grace_interest - grace_interest.round_dp(2)) * Decimal::from(grace_months);
+    let grace_payment = MonthPayment {
+        new_balance: total_amount,
+        current_amortization: dec!(0),
+        current_interest: grace_interest.round_dp(2),
+        accrued_interest: dec!(0),
+        admin_fee: dec!(0),
+        insurance: dec!(0),
+        principal_paid_to_date: dec!(0),
+        present_value: None,
+        applied_rate: None,
+        employer_subsidy: dec!(0),
+    };
+    curve.splice(0..0, std::iter::repeat_n(grace_payment, grace_months as usize));
+    let settled_in = match transition {
+        GraceTransition::PayInLastGraceMonth => {
+            curve.get_mut(grace_months as usize - 1).map(|p| (&mut p.current_interest, residual.round_dp(2)))
+        }
+        GraceTransition::RollIntoFirstInstallment => {
+            curve.get_mut(grace_months as usize).map(|p| (&mut p.accrued_interest, residual))
+        }
+    };
+    if let Some((interest, residual)) = settled_in {
+        *interest += residual;
+    }
+}
+
+/// Charges the MIP insurance premium, `mip_rate` on each month's opening balance.
+fn apply_mip(curve: &mut [MonthPayment], mip_rate: Decimal) {
+    for payment in curve {
+        payment.insurance = payment.opening_balance() * mip_rate;
+    }
+}
+
+/// Removes the insurance from every month and returns its sum, rounded to cents.
+fn take_insurance(curve: &mut [MonthPayment]) -> Decimal {
+    curve.iter_mut().map(|payment| std::mem::take(&mut payment.insurance)).sum::<Decimal>().round_dp(2)
+}
+
+/// Spreads `accrued_interest` evenly across the first `months` entries of the curve.
+fn spread_accrued_interest(curve: &mut [MonthPayment], accrued_interest: Decimal, months: u32) {
+    let months = (months as usize).min(curve.len());
+    if months == 0 {
+        return;
+    }
+
+    let share = accrued_interest / Decimal::from(months);
+    for payment in curve.iter_mut().take(months) {
+        payment.accrued_interest += share;
+    }
+}
+
+/// Sets the admin fee of each month from a per-month fee schedule.
+///
+/// Month `k` (zero-based) is charged `fees[k]`. When the schedule is shorter than the
+/// curve, the remaining months are charged its last value; an empty schedule charges
+/// nothing.
+fn apply_fee_schedule(curve: &mut [MonthPayment], fees: &[Decimal]) {
+    let Some(last_fee) = fees.last() else {
+        return;
+    };
+
+    for (month, payment) in curve.iter_mut().enumerate() {
+        payment.admin_fee = *fees.get(month).unwrap_or(last_fee);
+    }
+}
+
+/// A calendar month, used to anchor a schedule to real dates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct YearMonth {
+    /// The calendar year (e.g., 2025).
+    pub year: i32,
+    /// The calendar month, from 1 (January) to 12 (December).
+    pub month: u32,
+}
+
+impl YearMonth {
+    /// Returns the calendar month `offset` months after this one.
+    pub fn add_months(&self, offset: u32) -> YearMonth {
+        let zero_based = self.month as i64 - 1 + offset as i64;
+        YearMonth {
+            year: self.year + (zero_based / 12) as i32,
+            month: (zero_based % 12) as u32 + 1,
+        }
+    }
+
+    /// Returns the number of days in this calendar month, accounting for leap years.
+    pub fn days_in_month(&self) -> u32 {
+        match self.month {
+            2 if self.year % 4 == 0 && (self.year % 100 != 0 || self.year % 400 == 0) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    /// Returns the calendar quarter (1 to 4) of this month.
+    pub fn quarter(&self) -> u32 {
+        (self.month - 1) / 3 + 1
+    }
+}
+
+/// Aggregated payment figures for a single calendar quarter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct QuarterSummary {
+    /// The calendar year of the quarter.
+    pub year: i32,
+    /// The quarter of the year, from 1 to 4.
+    pub quarter: u32,
+    /// The sum of all installments paid in the quarter.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub total_installments: Decimal,
+    /// The sum of the interest paid in the quarter.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub total_interest: Decimal,
+    /// The sum of the principal amortized in the quarter.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub total_amortization: Decimal,
+    /// The remaining balance after the last payment of the quarter.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub ending_balance: Decimal,
+}
+
+/// Aggregates an amortization curve into calendar quarters.
+///
+/// The first entry of the curve is assumed to be paid in `start`, and each following
+/// entry in the next calendar month. Partial quarters at the start and end of the loan
+/// only aggregate the months they contain.
+///
+/// # Errors
+///
+/// Returns an error if `start.month` is not between 1 and 12.
+pub fn schedule_by_quarter(
+    amortization_curve: &[MonthPayment],
+    start: YearMonth,
+) -> Result<Vec<QuarterSummary>, FinancingError> {
+    if !(1..=12).contains(&start.month) {
+        return Err(FinancingError::InvalidArgument("Start month must be between 1 and 12.".to_string()));
+    }
+
+    let mut quarters: Vec<QuarterSummary> = Vec::new();
+
+    for (index, payment) in amortization_curve.iter().enumerate() {
+        let date = start.add_months(index as u32);
+        let quarter = date.quarter();
+
+        match quarters.last_mut() {
+            Some(summary) if summary.year == date.year && summary.quarter == quarter => {
+                summary.total_installments += payment.installment();
+                summary.total_interest += payment.current_interest;
+                summary.total_amortization += payment.current_amortization;
+                summary.ending_balance = payment.new_balance;
+            }
+            _ => quarters.push(QuarterSummary {
+                year: date.year,
+                quarter,
+                total_installments: payment.installment(),
+                total_interest: payment.current_interest,
+                total_amortization: payment.current_amortization,
+                ending_balance: payment.new_balance,
+            }),
+        }
+    }
+
+    Ok(quarters)
+}
+
+/// Derives the effective annual rate realized by an amortization curve.
+///
+/// This is the inverse of `normalize_annual_interest_rate`: the monthly rate is read
+/// from the first month as `interest / opening_balance` and annualized as
+/// `(1 + i)^12 - 1`. The result is a decimal (0.12 for 12%), not a percentage.
+///
+/// For indexed or floating loans the rate changes over time, so the value returned
+/// reflects only the first month's rate annualized.
+///
+/// Returns zero for an empty curve or a zero opening balance.
+pub fn effective_annual_rate(amortization_curve: &[MonthPayment]) -> Decimal {
+    let Some(first) = amortization_curve.first() else {
+        return dec!(0);
+    };
+
+    if first.opening_balance().is_zero() {
+        return dec!(0);
+    }
+
+    (dec!(1) + first.realized_rate()).powu(12) - dec!(1)
+}
+
+/// Splits each installment of a curve into its principal and interest fractions.
+///
+/// Returns one `(amortization_fraction, interest_fraction)` pair per month. Accrued
+/// signing interest counts as interest; fees and insurance are neither, so the pair sums
+/// to 1 only for months without them. Months with a zero installment yield `(0, 0)`.
+pub fn installment_split(amortization_curve: &[MonthPayment]) -> Vec<(Decimal, Decimal)> {
+    amortization_curve
+        .iter()
+        .map(|payment| {
+            let installment = payment.installment();
+            if installment.is_zero() {
+                return (dec!(0), dec!(0));
+            }
+
+            (
+                payment.current_amortization / installment,
+                (payment.current_interest + payment.accrued_interest) / installment,
+            )
+        })
+        .collect()
+}
+
+/// Calculates the net present value of a stream of monthly cash flows.
+///
+/// The first cash flow is discounted by one period, the second by two and so on, matching
+/// installments paid at the end of each month. The discount factor is divided down
+/// period by period instead of compounding `(1 + r)^k`, so long streams at high rates
+/// fade towards zero rather than overflowing.
+///
+/// # Arguments
+///
+/// * `cash_flows` - The cash flow of each period, in order.
+/// * `discount_rate` - The discount rate per period as a decimal (not percentage).
+pub fn npv(cash_flows: &[Decimal], discount_rate: Decimal) -> Decimal {
+    let factor = dec!(1) / (dec!(1) + discount_rate);
+    let mut discount = dec!(1);
+    let mut total = dec!(0);
+
+    for cash_flow in cash_flows {
+        discount *= factor;
+        total += cash_flow * discount;
+    }
+
+    total
+}
+
+/// Calculates the present-value savings of choosing SAC over Price.
+///
+/// Each system's installment stream is discounted with `npv` and the SAC value is
+/// subtracted from the Price value. A positive result means SAC is cheaper in present
+/// value terms; a negative result means Price is cheaper.
+///
+/// # Arguments
+///
+/// * `sac` - The SAC table to compare.
+/// * `price` - The Price table to compare.
+/// * `discount_rate` - The monthly discount rate as a decimal (not percentage).
+pub fn pv_savings_sac_vs_price(sac: &SacTableResult, price: &PriceTableResult, discount_rate: Decimal) -> Decimal {
+    let installments = |curve: &[MonthPayment]| -> Vec<Decimal> {
+        curve.iter().map(MonthPayment::installment).collect()
+    };
+
+    npv(&installments(&price.amortization_curve), discount_rate)
+        - npv(&installments(&sac.amortization_curve), discount_rate)
+}
+
+/// Finds the month at which the cumulative cost of one offer overtakes the other.
+///
+/// The running totals of the installments of both curves are compared month by month.
+/// The ordering is set by the first month in which the totals differ, and the first
+/// later month (1-based) in which it is strictly reversed is returned. Once a curve ends,
+/// its total stays constant, as the loan is paid off.
+///
+/// Returns `None` if the ordering never flips.
+pub fn cumulative_cost_crossover(a: &[MonthPayment], b: &[MonthPayment]) -> Option<u32> {
+    let mut total_a = dec!(0);
+    let mut total_b = dec!(0);
+    let mut initial_ordering = None;
+
+    for month in 0..a.len().max(b.len()) {
+        total_a += a.get(month).map(MonthPayment::installment).unwrap_or_default();
+        total_b += b.get(month).map(MonthPayment::installment).unwrap_or_default();
+
+        let ordering = total_a.cmp(&total_b);
+        if ordering.is_eq() {
+            continue;
+        }
+        match initial_ordering {
+            None => initial_ordering = Some(ordering),
+            Some(initial) if initial != ordering => return Some(month as u32 + 1),
+            Some(_) => {}
+        }
+    }
+
+    None
+}
+
+/// How Decimal fields are represented when serializing results to JSON.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JsonDecimals {
+    /// Decimals are emitted as strings, preserving full precision (the serde default).
+    #[default]
+    Strings,
+    /// Decimals are emitted as JSON numbers, monetary amounts rounded to two decimal
+    /// places and rates at full precision.
+    RoundedNumbers,
+}
+
+/// Serializes a value to JSON, choosing how its Decimal fields are represented.
+///
+/// With `JsonDecimals::Strings` this is the same as `serde_json::to_value`. With
+/// `JsonDecimals::RoundedNumbers` the Decimal fields of the result structs are emitted as
+/// JSON numbers, which front-ends can use without parsing: monetary amounts rounded to
+/// two decimals, rates at full precision. The choice is made per field type, so string
+/// fields are never converted.
+///
+/// # Errors
+///
+/// Returns an error if `value` cannot be serialized.
+pub fn to_json_value<T: Serialize>(value: &T, decimals: JsonDecimals) -> Result<serde_json::Value, serde_json::Error> {
+    let _representation = json_decimal::Representation::set(decimals);
+    serde_json::to_value(value)
+}
+
+/// Serializers for the Decimal fields of the result structs, following the
+/// representation selected by `to_json_value` on the current thread.
+mod json_decimal {
+    use std::cell::Cell;
+
+    use rust_decimal::Decimal;
+    use rust_decimal::prelude::ToPrimitive;
+    use serde::{Serialize, Serializer};
+
+    use super::JsonDecimals;
+
+    thread_local! {
+        static CURRENT: Cell<JsonDecimals> = const { Cell::new(JsonDecimals::Strings) };
+    }
+
+    /// Selects a representation until dropped, then restores the previous one.
+    pub(super) struct Representation(JsonDecimals);
+
+    impl Representation {
+        pub(super) fn set(decimals: JsonDecimals) -> Self {
+            Representation(CURRENT.with(|current| current.replace(decimals)))
+        }
+    }
+
+    impl Drop for Representation {
+        fn drop(&mut self) {
+            CURRENT.with(|current| current.set(self.0));
+        }
+    }
+
+    /// Serializes a monetary amount, rounded to cents when numbers are selected.
+    pub(super) fn serialize<S: Serializer>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_number(value, Some(2), serializer)
+    }
+
+    /// Serializes a rate, which keeps its full precision in both representations.
+    pub(super) fn serialize_rate<S: Serializer>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_number(value, None, serializer)
+    }
+
+    pub(super) fn serialize_option<S: Serializer>(value: &Option<Decimal>, serializer: S) -> Result<S::Ok, S::Error> {
+        value.as_ref().map(|value| Field(value, Some(2))).serialize(serializer)
+    }
+
+    pub(super) fn serialize_rate_option<S: Serializer>(
+        value: &Option<Decimal>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.as_ref().map(|value| Field(value, None)).serialize(serializer)
+    }
+
+    struct Field<'a>(&'a Decimal, Option<u32>);
+
+    impl Serialize for Field<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize_number(self.0, self.1, serializer)
+        }
+    }
+
+    fn serialize_number<S: Serializer>(value: &Decimal, scale: Option<u32>, serializer: S) -> Result<S::Ok, S::Error> {
+        if CURRENT.with(Cell::get) == JsonDecimals::RoundedNumbers
+            && let Some(number) = scale.map_or(*value, |scale| value.round_dp(scale)).to_f64()
+        {
+            return serializer.serialize_f64(number);
+        }
+
+        Serialize::serialize(value, serializer)
+    }
+}
+
+/// A fluent builder for loans that combine several optional features.
+///
+/// It fills a `LoanConfig`, so simple loans stay a three-call chain and advanced features
+/// compose without spelling out the whole struct:
+///
+/// ```
+/// use br_financial::LoanBuilder;
+/// use rust_decimal_macros::dec;
+///
+/// let sac = LoanBuilder::new()
+///     .principal(dec!(300000))
+///     .annual_rate(dec!(10.5))
+///     .months(360)
+///     .with_mip(dec!(0.0002))
+///     .with_grace(6)
+///     .with_prepayment(24, dec!(20000))
+///     .sac()
+///     .unwrap();
+/// assert!(sac.total_paid > dec!(300000));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct LoanBuilder {
+    config: LoanConfig,
+}
+
+impl LoanBuilder {
+    /// Creates a builder with the `LoanConfig` defaults.
+    pub fn new() -> Self {
+        LoanBuilder::default()
+    }
+
+    /// Sets the total principal amount of the loan.
+    pub fn principal(mut self, total_amount: Decimal) -> Self {
+        self.config.total_amount = total_amount;
+        self
+    }
+
+    /// Sets the annual interest rate as a percentage (e.g., 10.5 for 10.5%).
+    pub fn annual_rate(mut self, interest_per_year: Decimal) -> Self {
+        self.config.interest_per_year = interest_per_year;
+        self
+    }
+
+    /// Sets the number of amortizing installments.
+    pub fn months(mut self, total_months: u32) -> Self {
+        self.config.total_months = total_months;
+        self
+    }
+
+    /// Sets the down payment as a percentage of the principal.
+    pub fn down_payment_percent(mut self, down_payment_percent: Decimal) -> Self {
+        self.config.down_payment_percent = down_payment_percent;
+        self
+    }
+
+    /// Charges MIP insurance at a monthly rate on the opening balance.
+    pub fn with_mip(mut self, mip_rate: Decimal) -> Self {
+        self.config.mip_rate = Some(mip_rate);
+        self
+    }
+
+    /// Adds interest-only months (carência) before amortization starts.
+    pub fn with_grace(mut self, grace_months: u32) -> Self {
+        self.config.grace_months = grace_months;
+        self
+    }
+
+    /// Adds a lump-sum prepayment after the given amortizing installment (1-based). The
+    /// calculation fails if the installment is not reached, e.g. because earlier
+    /// prepayments shortened the term.
+    pub fn with_prepayment(mut self, month: u32, amount: Decimal) -> Self {
+        self.config.prepayments.push((month, amount));
+        self
+    }
+
+    /// Sets how the prepayments are absorbed. Defaults to `PrepaymentStrategy::ReduceTerm`.
+    pub fn with_prepayment_strategy(mut self, strategy: PrepaymentStrategy) -> Self {
+        self.config.prepayment_strategy = strategy;
+        self
+    }
+
+    /// Adds an extra principal payment to every installment.
+    pub fn with_monthly_extra(mut self, extra: Decimal) -> Self {
+        self.config.monthly_extra = Some(extra);
+        self
+    }
+
+    /// Sets the administrative fee schedule, see `LoanConfig::monthly_fees`.
+    pub fn with_fees(mut self, monthly_fees: Vec<Decimal>) -> Self {
+        self.config.monthly_fees = monthly_fees;
+        self
+    }
+
+    /// Returns the configuration built so far.
+    pub fn build(self) -> LoanConfig {
+        self.config
+    }
+
+    /// Calculates both tables with `calculate`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `calculate`.
+    pub fn calculate(&self) -> Result<DebtTrajectoryResult, FinancingError> {
+        calculate(&self.config)
+    }
+
+    /// Calculates the loan and returns its SAC table.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `calculate`.
+    pub fn sac(&self) -> Result<SacTableResult, FinancingError> {
+        self.calculate().map(|result| result.sac_table)
+    }
+
+    /// Calculates the loan and returns its Price table.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `calculate`.
+    pub fn price(&self) -> Result<PriceTableResult, FinancingError> {
+        self.calculate().map(|result| result.price_table)
+    }
+}
+
+/// Finds a root of `f` in `[low, high]` by bisection.
+///
+/// Returns `None` if `f(low)` and `f(high)` have the same sign. Iterates until the
+/// interval is narrower than `1e-12` or 200 iterations have run.
+fn bisect(mut f: impl FnMut(Decimal) -> Decimal, mut low: Decimal, mut high: Decimal) -> Option<Decimal> {
+    let tolerance = dec!(0.000000000001);
+    let mut f_low = f(low);
+    let f_high = f(high);
+
+    if f_low.is_zero() {
+        return Some(low);
+    }
+    if f_high.is_zero() {
+        return Some(high);
+    }
+    if f_low.is_sign_negative() == f_high.is_sign_negative() {
+        return None;
+    }
+
+    for _ in 0..200 {
+        let mid = (low + high) / dec!(2);
+        let f_mid = f(mid);
+        if f_mid.is_zero() || high - low < tolerance {
+            return Some(mid);
+        }
+        if f_mid.is_sign_negative() == f_low.is_sign_negative() {
+            low = mid;
+            f_low = f_mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    Some((low + high) / dec!(2))
+}
+
+/// Returns the monthly loan rate below which Price becomes preferable to SAC for a
+/// borrower who reinvests at `reinvest_rate`.
+///
+/// Under the reinvested-difference model, Price wins when `pv_savings_sac_vs_price`
+/// discounted at `reinvest_rate` is negative. Discounted at the loan's own rate, both
+/// installment streams are worth exactly the principal, and Price's later cash flows lose
+/// more value as the rate rises. The sign therefore flips where the loan rate equals the
+/// reinvestment rate, whatever the amount and term: below it Price is preferable, above it
+/// SAC.
+///
+/// Returns `None` when nothing flips: a single-month term, where both systems charge the
+/// same installment, a non-positive amount, or a non-positive reinvestment rate.
+pub fn rate_flip_threshold(total_amount: Decimal, total_months: u32, reinvest_rate: Decimal) -> Option<Decimal> {
+    (total_months > 1 && total_amount > dec!(0) && reinvest_rate > dec!(0)).then_some(reinvest_rate)
+}
+
+/// Builds an amortization curve where each month may be charged a different rate.
+///
+/// `rate_for_month` receives the zero-based month and returns the monthly rate applied to
+/// it. In SAC the amortization stays `P / n`; in Price the installment is recomputed every
+/// month from the outstanding balance, the remaining term and that month's rate, which is
+/// how floating-rate Price contracts are re-amortized.
+fn variable_rate_curve(
+    total_amount: Decimal,
+    total_months: u32,
+    mut rate_for_month: impl FnMut(u32) -> Decimal,
+    system: AmortizationSystem,
+) -> Vec<MonthPayment> {
+    let fixed_amortization = total_amount / Decimal::from(total_months);
+    let mut current_balance = total_amount;
+    let mut amortization_curve = Vec::with_capacity(total_months as usize);
+
+    for month in 0..total_months {
+        let rate = rate_for_month(month);
+        let is_last_month = month == total_months - 1;
+        current_balance = current_balance.max(dec!(0));
+        let interest_payment = current_balance * rate;
+        let amortization = match system {
+            AmortizationSystem::Sac => sac_month_amortization(current_balance, fixed_amortization, is_last_month),
+            AmortizationSystem::Price => {
+                price_fixed_payment(current_balance, rate, total_months - month) - interest_payment
+            }
+        };
+
+        current_balance -= amortization;
+        amortization_curve.push(
+            MonthPayment {
+                new_balance: current_balance.max(dec!(0)),
+                current_amortization: amortization,
+                current_interest: interest_payment,
+                accrued_interest: dec!(0),
+                admin_fee: dec!(0),
+                insurance: dec!(0),
+                principal_paid_to_date: total_amount - current_balance,
+                present_value: None,
+                applied_rate: None,
+                employer_subsidy: dec!(0),
+            }
+        );
+        #[cfg(feature = "tracing")]
+        trace_month(0, &amortization_curve);
+    }
+
+    amortization_curve
+}
+
+/// Calculates the Price installment when the first one is paid at signing.
+///
+/// An annuity-due is worth `(1 + i)` times the ordinary annuity with the same payment, so
+/// the installment is the ordinary one divided by that factor:
+/// `PMT_due = P * [i(1 + i)^n] / [(1 + i)^n – 1] / (1 + i)`.
+fn annuity_due_payment(total_amount: Decimal, monthly_interest_rate: Decimal, total_months: u32) -> Decimal {
+    price_fixed_payment(total_amount, monthly_interest_rate, total_months) / (dec!(1) + monthly_interest_rate)
+}
+
+/// Builds the Price curve of an annuity-due: the first installment, paid at signing, is
+/// all amortization; each later one pays interest on the balance left by the previous
+/// one. The last month amortizes whatever balance remains.
+fn annuity_due_curve(
+    total_amount: Decimal,
+    fixed_payment: Decimal,
+    monthly_interest_rate: Decimal,
+    total_months: u32,
+) -> Vec<MonthPayment> {
+    let mut current_balance = total_amount;
+    let mut amortization_curve = Vec::with_capacity(total_months as usize);
+
+    for month in 0..total_months {
+        let interest_payment = if month == 0 { dec!(0) } else { current_balance * monthly_interest_rate };
+        let amortization = if month == total_months - 1 {
+            current_balance
+        } else {
+            fixed_payment - interest_payment
+        };
+
+        current_balance -= amortization;
+        amortization_curve.push(
+            MonthPayment {
+                new_balance: current_balance.max(dec!(0)),
+                current_amortization: amortization,
+                current_interest: interest_payment,
+                accrued_interest: dec!(0),
+                admin_fee: dec!(0),
+                insurance: dec!(0),
+                principal_paid_to_date: total_amount - current_balance,
+                present_value: None,
+                applied_rate: None,
+                employer_subsidy: dec!(0),
+            }
+        );
+        #[cfg(feature = "tracing")]
+        trace_month(0, &amortization_curve);
+    }
+
+    amortization_curve
+}
+
+/// Builds a Price curve whose installments follow seasonal weights by calendar month.
+///
+/// Installment `k` (1-based) is `w_k * X`, where `w_k` is the weight of its calendar
+/// month and `X = P / Σ w_k (1 + i)^-k`, so the installments still repay the principal
+/// at the contract rate. Returns `X` with the curve; the last month amortizes whatever
+/// balance remains. The weights must have passed `validate_seasonal_weights`.
+fn seasonal_price_curve(
+    total_amount: Decimal,
+    monthly_interest_rate: Decimal,
+    total_months: u32,
+    weights: &[Decimal],
+    first_payment_month: YearMonth,
+) -> (Decimal, Vec<MonthPayment>) {
+    let weight_for_month = |month: u32| weights[first_payment_month.add_months(month).month as usize - 1];
+    let month_weights: Vec<Decimal> = (0..total_months).map(weight_for_month).collect();
+    let base_payment = total_amount / npv(&month_weights, monthly_interest_rate);
+
+    let mut current_balance = total_amount;
+    let mut amortization_curve = Vec::with_capacity(total_months as usize);
+    for (month, weight) in month_weights.iter().enumerate() {
+        let interest_payment = current_balance * monthly_interest_rate;
+        let amortization = if month as u32 == total_months - 1 {
+            current_balance
+        } else {
+            base_payment * weight - interest_payment
+        };
+
+        current_balance -= amortization;
+        amortization_curve.push(
+            MonthPayment {
+                new_balance: current_balance.max(dec!(0)),
+                current_amortization: amortization,
+                current_interest: interest_payment,
+                accrued_interest: dec!(0),
+                admin_fee: dec!(0),
+                insurance: dec!(0),
+                principal_paid_to_date: total_amount - current_balance,
+                present_value: None,
+                applied_rate: None,
+                employer_subsidy: dec!(0),
+            }
+        );
+        #[cfg(feature = "tracing")]
+        trace_month(0, &amortization_curve);
+    }
+
+    (base_payment, amortization_curve)
+}
+
+/// Checks that seasonal weights are 12 non-negative values summing to 12, and that the
+/// calendar months of the term, starting at `first_payment_month`, do not all weigh
+/// zero, which would leave no installment to repay the loan.
+fn validate_seasonal_weights(
+    weights: &[Decimal],
+    first_payment_month: YearMonth,
+    total_months: u32,
+) -> Result<(), FinancingError> {
+    if weights.len() != 12
+        || weights.iter().any(|weight| *weight < dec!(0))
+        || weights.iter().sum::<Decimal>() != dec!(12)
+    {
+        return Err(FinancingError::InvalidArgument(
+            "Seasonal weights must be 12 non-negative values summing to 12.".to_string(),
+        ));
+    }
+    let term_weight: Decimal = (0..total_months.min(12))
+        .map(|month| weights[first_payment_month.add_months(month).month as usize - 1])
+        .sum();
+    if term_weight.is_zero() {
+        return Err(FinancingError::InvalidArgument(
+            "Seasonal weights of the term's calendar months cannot all be zero.".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Shortens the last period of a curve to `fraction` of a month: its interest is
+/// compounded at the month's realized rate for only that fraction, `(1 + i)^f - 1`, and
+/// the whole opening balance is amortized.
+fn apply_final_stub(amortization_curve: &mut [MonthPayment], fraction: Decimal) {
+    let Some(last) = amortization_curve.last_mut() else {
+        return;
+    };
+
+    let opening_balance = last.opening_balance();
+    let stub_rate = (dec!(1) + last.realized_rate()).powd(fraction) - dec!(1);
+    last.current_interest = opening_balance * stub_rate;
+    last.current_amortization = opening_balance;
+    last.principal_paid_to_date += last.new_balance;
+    last.new_balance = dec!(0);
+}
+
+/// Builds an amortization curve whose interest accrues on the actual days of each period,
+/// one period per entry of `period_days`.
+///
+/// The installment is solved on the same accrual basis. Each period grows the balance by
+/// `f_k = 1 + annual_rate * days_k / 365`, so the Price installment that repays the loan
+/// is `P * prod(f) / sum_k prod(f_j for j > k)`; SAC keeps the exact amortization `P / n`.
+/// The last month settles the sub-cent remainder. Returns the unrounded installment (Price)
+/// or amortization (SAC) together with the curve.
+fn actual_day_count_curve(
+    total_amount: Decimal,
+    annual_rate: Decimal,
+    period_days: &[u32],
+    system: AmortizationSystem,
+) -> (Decimal, Vec<MonthPayment>) {
+    let growth = |days: u32| dec!(1) + annual_rate * Decimal::from(days) / dec!(365);
+    let fixed_value = match system {
+        AmortizationSystem::Sac => total_amount / Decimal::from(period_days.len() as u32),
+        AmortizationSystem::Price => {
+            // Walking backwards, `later_growth` is the growth of the periods after the
+            // current one and `payment_weights` the value at the end of the loan of one
+            // unit paid in each period.
+            let mut later_growth = dec!(1);
+            let mut payment_weights = dec!(0);
+            for days in period_days.iter().rev() {
+                payment_weights += later_growth;
+                later_growth *= growth(*days);
+            }
+            total_amount * later_growth / payment_weights
+        }
+    };
+    let mut current_balance = total_amount;
+    let mut amortization_curve = Vec::with_capacity(period_days.len());
+
+    for (month, days) in period_days.iter().enumerate() {
+        let interest_payment = current_balance * annual_rate * Decimal::from(*days) / dec!(365);
+        let amortization = if month == period_days.len() - 1 {
+            current_balance
+        } else {
+            match system {
+                AmortizationSystem::Sac => fixed_value.min(current_balance),
+                AmortizationSystem::Price => fixed_value - interest_payment,
+            }
+        };
+
+        current_balance -= amortization;
+        amortization_curve.push(
+            MonthPayment {
+                new_balance: current_balance.max(dec!(0)),
+                current_amortization: amortization,
+                current_interest: interest_payment,
+                accrued_interest: dec!(0),
+                admin_fee: dec!(0),
+                insurance: dec!(0),
+                principal_paid_to_date: total_amount - current_balance,
+                present_value: None,
+                applied_rate: None,
+                employer_subsidy: dec!(0),
+            }
+        );
+        #[cfg(feature = "tracing")]
+        trace_month(0, &amortization_curve);
+    }
+
+    (fixed_value, amortization_curve)
+}
+
+/// Calculates the total paid when each month is charged the rate of a simulated path.
+///
+/// This is the building block for Monte Carlo analysis of floating-rate loans: month `k`
+/// (zero-based) is charged `rate_path[k]`. A path shorter than the term is padded with its
+/// last rate; extra entries are ignored.
+///
+/// # Arguments
+///
+/// * `total_amount` - The principal loan amount.
+/// * `total_months` - The total number of payments.
+/// * `rate_path` - The monthly rates, as decimals (not percentages), in order.
+/// * `system` - The amortization system used to build the schedule.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero or `rate_path` is empty.
+pub fn total_paid_with_rate_path(
+    total_amount: Decimal,
+    total_months: u32,
+    rate_path: &[Decimal],
+    system: AmortizationSystem,
+) -> Result<Decimal, FinancingError> {
+    let curve = calculate_with_rate_path(total_amount, total_months, rate_path, RateCollar::default(), system)?;
+
+    Ok(curve.iter().map(MonthPayment::installment).sum::<Decimal>().round_dp(2))
+}
+
+/// Optional bounds on the monthly rate of a floating loan (a collar).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateCollar {
+    /// The lowest monthly rate charged, as a decimal.
+    #[serde(default)]
+    pub rate_floor: Option<Decimal>,
+    /// The highest monthly rate charged, as a decimal.
+    #[serde(default)]
+    pub rate_ceiling: Option<Decimal>,
+}
+
+impl RateCollar {
+    /// Checks that the floor is not above the ceiling.
+    fn validate(&self) -> Result<(), FinancingError> {
+        if let (Some(floor), Some(ceiling)) = (self.rate_floor, self.rate_ceiling)
+            && floor > ceiling
+        {
+            return Err(FinancingError::InvalidArgument("Rate floor cannot be above the ceiling.".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Clamps a monthly rate between the floor and the ceiling, when set.
+    pub fn clamp(&self, monthly_rate: Decimal) -> Decimal {
+        let floored = self.rate_floor.map_or(monthly_rate, |floor| monthly_rate.max(floor));
+        self.rate_ceiling.map_or(floored, |ceiling| floored.min(ceiling))
+    }
+}
+
+/// Builds the schedule of a loan charged a monthly rate path clamped by a collar.
+///
+/// Month `k` (zero-based) is charged `collar.clamp(rate_path[k])`; a path shorter than
+/// the term is padded with its last rate. The clamped rate is reported in each month's
+/// `applied_rate`. As in `total_paid_with_rate_path`, Price re-amortizes every month and
+/// SAC keeps a constant amortization.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero, `rate_path` is empty, or the floor is
+/// above the ceiling.
+pub fn calculate_with_rate_path(
+    total_amount: Decimal,
+    total_months: u32,
+    rate_path: &[Decimal],
+    collar: RateCollar,
+    system: AmortizationSystem,
+) -> Result<Vec<MonthPayment>, FinancingError> {
+    if total_months == 0 {
+        return Err(FinancingError::ZeroMonths);
+    }
+    let Some(last_rate) = rate_path.last() else {
+        return Err(FinancingError::InvalidArgument("Rate path cannot be empty.".to_string()));
+    };
+    collar.validate()?;
+
+    let rate_for_month = |month: u32| collar.clamp(*rate_path.get(month as usize).unwrap_or(last_rate));
+    let mut curve = variable_rate_curve(total_amount, total_months, rate_for_month, system);
+    for (month, payment) in curve.iter_mut().enumerate() {
+        payment.applied_rate = Some(rate_for_month(month as u32));
+    }
+
+    Ok(curve)
+}
+
+/// The headline numbers of a financing quote for both systems.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct QuickQuote {
+    /// The first (highest) SAC installment.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub sac_first: Decimal,
+    /// The last (lowest) SAC installment.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub sac_last: Decimal,
+    /// The total paid over the loan under SAC.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub sac_total: Decimal,
+    /// The fixed Price installment.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub price_installment: Decimal,
+    /// The total paid over the loan under Price.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub price_total: Decimal,
+}
+
+/// Calculates the headline numbers of both systems without building the schedules.
+///
+/// Uses closed forms only: the Price installment formula, and for SAC
+/// `first = P/n + P*i`, `last = (P/n)(1 + i)` and `total = P + P*i*(n + 1)/2`.
+///
+/// # Arguments
+///
+/// * `total_amount` - The principal loan amount.
+/// * `interest_per_year` - The annual interest rate as a percentage (e.g., 10.5 for 10.5%).
+/// * `total_months` - The total number of payments.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero or the interest rate is at or below -100%.
+pub fn quick_quote(total_amount: Decimal, interest_per_year: Decimal, total_months: u32) -> Result<QuickQuote, FinancingError> {
+    if total_months == 0 {
+        return Err(FinancingError::ZeroMonths);
+    }
+
+    let rate = normalize_rate(interest_per_year, 12)?;
+    let months = Decimal::from(total_months);
+    let fixed_amortization = total_amount / months;
+    let price_installment = price_fixed_payment(total_amount, rate, total_months);
+
+    Ok(QuickQuote {
+        sac_first: (fixed_amortization + total_amount * rate).round_dp(2),
+        sac_last: (fixed_amortization * (dec!(1) + rate)).round_dp(2),
+        sac_total: (total_amount + total_amount * rate * (months + dec!(1)) / dec!(2)).round_dp(2),
+        price_installment: price_installment.round_dp(2),
+        price_total: (price_installment * months).round_dp(2),
+    })
+}
+
+/// Returns a copy of the curve with each month's `present_value` populated.
+///
+/// The installment of month `k` (1-based) is discounted by `(1 + discount_rate)^k`. When
+/// discounted at the loan's own rate, and with no fees, the present values add up to the
+/// principal.
+///
+/// # Arguments
+///
+/// * `amortization_curve` - The schedule to value.
+/// * `discount_rate` - The monthly discount rate as a decimal (not percentage).
+pub fn with_present_value(amortization_curve: &[MonthPayment], discount_rate: Decimal) -> Vec<MonthPayment> {
+    let factor = dec!(1) / (dec!(1) + discount_rate);
+    let mut discount = dec!(1);
+
+    amortization_curve
+        .iter()
+        .map(|payment| {
+            discount *= factor;
+            MonthPayment { present_value: Some(payment.installment() * discount), ..payment.clone() }
+        })
+        .collect()
+}
+
+/// Calculates the prepayment needed to bring the installment down to a target.
+///
+/// Under `PrepaymentStrategy::ReduceInstallment`, the lump sum is applied right after the
+/// installment of `at_month` (1-based) is paid and the remaining term is kept. The
+/// rate is read from that month of the curve. For Price the recomputed installment is
+/// linear in the balance, so the new balance is `target / PMT(1, i, n)`; for SAC it is
+/// the first recomputed installment, `B/n + B*i`, that must match the target.
+///
+/// Returns zero if the installment is already at or below the target.
+///
+/// # Arguments
+///
+/// * `amortization_curve` - The schedule before the prepayment.
+/// * `at_month` - The month (1-based) after which the prepayment is made.
+/// * `target_installment` - The desired installment after the prepayment.
+/// * `system` - The amortization system of the schedule.
+///
+/// # Errors
+///
+/// Returns an error if `at_month` does not leave at least one remaining installment, if
+/// the target is not positive, since only paying off the whole balance reaches it, or if
+/// the target is below the interest-only floor `balance * i` of the remaining balance.
+pub fn prepayment_for_installment(
+    amortization_curve: &[MonthPayment],
+    at_month: u32,
+    target_installment: Decimal,
+    system: AmortizationSystem,
+) -> Result<Decimal, FinancingError> {
+    let at_month = at_month as usize;
+    if at_month == 0 || at_month >= amortization_curve.len() {
+        return Err(FinancingError::InvalidArgument(
+            "Prepayment month must leave at least one remaining installment.".to_string(),
+        ));
+    }
+    if target_installment <= dec!(0) {
+        return Err(FinancingError::InvalidArgument(
+            "Target installment must be positive; only a full payoff removes it.".to_string(),
+        ));
+    }
+
+    let balance = amortization_curve[at_month - 1].new_balance;
+    let rate = amortization_curve[at_month - 1].realized_rate();
+    let remaining_months = (amortization_curve.len() - at_month) as u32;
+    if target_installment < balance * rate {
+        return Err(FinancingError::InvalidArgument(
+            "Target installment is below the interest-only floor of the remaining balance.".to_string(),
+        ));
+    }
+
+    let installment_per_unit = match system {
+        AmortizationSystem::Price => price_fixed_payment(dec!(1), rate, remaining_months),
+        AmortizationSystem::Sac => dec!(1) / Decimal::from(remaining_months) + rate,
+    };
+    let target_balance = target_installment / installment_per_unit;
+
+    Ok((balance - target_balance).max(dec!(0)))
+}
+
+/// Builds a schedule where interest is charged only at the end of each capitalization
+/// period while installments remain monthly.
+///
+/// Accrual timing: every month, interest accrues on that month's opening balance at
+/// `monthly_interest_rate` (without compounding inside the period). The accrued interest
+/// is charged in the installment of the month that closes each period, i.e. months
+/// (1-based) divisible by `capitalization_months`, and in the final month. Other months
+/// are amortization-only. With `capitalization_months = 2` odd months carry no interest
+/// and even months carry two months of it.
+///
+/// In SAC the amortization stays `P / n`. In Price the installment stays the standard
+/// fixed payment and its principal portion absorbs the uneven interest; the final month
+/// amortizes whatever balance remains.
+///
+/// A `capitalization_months` of 1 reproduces the regular monthly schedules.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` or `capitalization_months` is zero.
+pub fn capitalized_interest_curve(
+    total_amount: Decimal,
+    monthly_interest_rate: Decimal,
+    total_months: u32,
+    capitalization_months: u32,
+    system: AmortizationSystem,
+) -> Result<Vec<MonthPayment>, FinancingError> {
+    if total_months == 0 {
+        return Err(FinancingError::ZeroMonths);
+    }
+    if capitalization_months == 0 {
+        return Err(FinancingError::InvalidArgument("Capitalization period cannot be zero.".to_string()));
+    }
+
+    let fixed_amortization = total_amount / Decimal::from(total_months);
+    let fixed_payment = price_fixed_payment(total_amount, monthly_interest_rate, total_months);
+    let mut current_balance = total_amount;
+    let mut accrued = dec!(0);
+    let mut amortization_curve = Vec::with_capacity(total_months as usize);
+
+    for month in 0..total_months {
+        let is_last_month = month == total_months - 1;
+        current_balance = current_balance.max(dec!(0));
+        accrued += current_balance * monthly_interest_rate;
+
+        let interest_payment = if (month + 1) % capitalization_months == 0 || is_last_month {
+            std::mem::take(&mut accrued)
+        } else {
+            dec!(0)
+        };
+        let amortization = match system {
+            AmortizationSystem::Sac => sac_month_amortization(current_balance, fixed_amortization, is_last_month),
+            AmortizationSystem::Price if is_last_month => current_balance,
+            AmortizationSystem::Price => (fixed_payment - interest_payment).min(current_balance),
+        };
+
+        current_balance -= amortization;
+        amortization_curve.push(
+            MonthPayment {
+                new_balance: current_balance.max(dec!(0)),
+                current_amortization: amortization,
+                current_interest: interest_payment,
+                accrued_interest: dec!(0),
+                admin_fee: dec!(0),
+                insurance: dec!(0),
+                principal_paid_to_date: total_amount - current_balance,
+                present_value: None,
+                applied_rate: None,
+                employer_subsidy: dec!(0),
+            }
+        );
+        #[cfg(feature = "tracing")]
+        trace_month(0, &amortization_curve);
+    }
+
+    Ok(amortization_curve)
+}
+
+/// Calculates the debt trajectory of many independent inputs.
+///
+/// Results are returned in the same order as `inputs`. With the `rayon` feature the
+/// inputs are processed in parallel on rayon's thread pool; without it they are processed
+/// sequentially.
+pub fn calculate_batch(inputs: &[DebtCalculationInput]) -> Vec<Result<DebtTrajectoryResult, FinancingError>> {
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+
+        inputs.par_iter().cloned().map(|input| calculate(&LoanConfig::from(input))).collect()
+    }
+
+    #[cfg(not(feature = "rayon"))]
+    {
+        inputs.iter().cloned().map(|input| calculate(&LoanConfig::from(input))).collect()
+    }
+}
+
+/// The installment in effect when a rate step starts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct RateStepInstallment {
+    /// The month (1-based) in which the step starts.
+    pub start_month: u32,
+    /// The annual interest rate of the step as a percentage.
+    #[serde(serialize_with = "json_decimal::serialize_rate")]
+    pub interest_per_year: Decimal,
+    /// The installment of the first month of the step.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub installment: Decimal,
+}
+
+/// The results of a schedule calculated under annually stepped rates.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct SteppedRateResult {
+    /// The installment at the start of each step within the term.
+    pub step_installments: Vec<RateStepInstallment>,
+    /// The total amount paid over the lifetime of the loan.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub total_paid: Decimal,
+    /// A vector containing the payment details for each month.
+    pub amortization_curve: Vec<MonthPayment>,
+}
+
+/// Calculates a schedule where the rate changes at given months, such as a teaser rate
+/// for the first year followed by a higher rate.
+///
+/// Each step is a `(start_month, interest_per_year)` pair, with `start_month` 1-based and
+/// the annual rate as a percentage; a step applies until the next one starts. In Price
+/// the installment is recomputed from the outstanding balance and remaining term when a
+/// step starts; in SAC the amortization stays constant and only the interest changes.
+///
+/// Each step's monthly rate is clamped by `collar`, as in `calculate_with_rate_path`, and
+/// reported in each month's `applied_rate`; `step_installments` keeps the annual rates
+/// as given.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero, if the steps are empty, do not start at
+/// month 1 or are not strictly increasing, if any rate is at or below -100%, or if the
+/// floor is above the ceiling.
+pub fn calculate_with_rate_steps(
+    total_amount: Decimal,
+    total_months: u32,
+    steps: &[(u32, Decimal)],
+    collar: RateCollar,
+    system: AmortizationSystem,
+) -> Result<SteppedRateResult, FinancingError> {
+    if total_months == 0 {
+        return Err(FinancingError::ZeroMonths);
+    }
+    if steps.first().map(|(start, _)| *start) != Some(1) {
+        return Err(FinancingError::InvalidArgument("Rate steps must start at month 1.".to_string()));
+    }
+    if steps.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+        return Err(FinancingError::InvalidArgument("Rate steps must be sorted by start month.".to_string()));
+    }
+    collar.validate()?;
+
+    let monthly_rates = steps
+        .iter()
+        .map(|(_, rate)| normalize_rate(*rate, 12).map(|monthly_rate| collar.clamp(monthly_rate)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let step_index = |month: u32| steps.iter().rposition(|(start, _)| *start <= month + 1).unwrap_or(0);
+    let rate_for_month = |month: u32| monthly_rates[step_index(month)];
+
+    let mut amortization_curve = variable_rate_curve(total_amount, total_months, rate_for_month, system);
+    for (month, payment) in amortization_curve.iter_mut().enumerate() {
+        payment.applied_rate = Some(rate_for_month(month as u32));
+    }
+
+    let step_installments = steps
+        .iter()
+        .filter(|(start, _)| *start <= total_months)
+        .map(|(start, rate)| RateStepInstallment {
+            start_month: *start,
+            interest_per_year: *rate,
+            installment: amortization_curve[*start as usize - 1].installment().round_dp(2),
+        })
+        .collect();
+    let total_paid = amortization_curve.iter().map(MonthPayment::installment).sum::<Decimal>().round_dp(2);
+
+    Ok(SteppedRateResult { step_installments, total_paid, amortization_curve })
+}
+
+/// Sums the interest charged over a curve, including accrued signing interest.
+pub fn total_interest(amortization_curve: &[MonthPayment]) -> Decimal {
+    amortization_curve
+        .iter()
+        .map(|payment| payment.current_interest + payment.accrued_interest)
+        .sum()
+}
+
+/// Calculates how much interest a prepayment saved compared to the original schedule.
+///
+/// This is the difference between the total interest of both curves, so it applies to
+/// schedules recomputed under either `PrepaymentStrategy`.
+pub fn interest_saved_by_prepayment(original: &[MonthPayment], after_prepayment: &[MonthPayment]) -> Decimal {
+    total_interest(original) - total_interest(after_prepayment)
+}
+
+/// Calculates a declining schedule whose final installment matches a target.
+///
+/// Installments decrease linearly from a first payment `F` to `target_last`, like SAC,
+/// but calibrated to end at a chosen amount. `F` is solved so the installments, discounted
+/// at the loan rate, repay the principal exactly: with `v = 1/(1 + i)`,
+/// `A = Σ v^k` and `B = Σ ((k - 1)/(n - 1)) v^k`, `F = (P - L*B) / (A - B)`. Each month
+/// charges interest on the outstanding balance and amortizes the rest of the installment.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero, or if the target is infeasible: when it
+/// would require increasing installments, when some month would not cover its interest,
+/// or, for a single-month loan, when it differs from `P(1 + i)` by more than a cent.
+pub fn calculate_target_last_payment(
+    total_amount: Decimal,
+    monthly_interest_rate: Decimal,
+    total_months: u32,
+    target_last: Decimal,
+) -> Result<Vec<MonthPayment>, FinancingError> {
+    let infeasible = || FinancingError::InvalidArgument(
+        "Target last payment is infeasible for a declining schedule.".to_string(),
+    );
+    if total_months == 0 {
+        return Err(FinancingError::ZeroMonths);
+    }
+    if total_months == 1 {
+        let payment = total_amount * (dec!(1) + monthly_interest_rate);
+        if (payment - target_last).abs() > dec!(0.01) {
+            return Err(infeasible());
+        }
+        return build_sac_table(total_amount, monthly_interest_rate.into(), 1).map(|table| table.amortization_curve);
+    }
+
+    let factor = dec!(1) / (dec!(1) + monthly_interest_rate);
+    let steps = Decimal::from(total_months - 1);
+    let mut discount = dec!(1);
+    let mut annuity = dec!(0);
+    let mut ramp = dec!(0);
+    for k in 0..total_months {
+        discount *= factor;
+        annuity += discount;
+        ramp += Decimal::from(k) / steps * discount;
+    }
+
+    let first_payment = (total_amount - target_last * ramp) / (annuity - ramp);
+    if first_payment < target_last {
+        return Err(infeasible());
+    }
+
+    let step = (first_payment - target_last) / steps;
+    let mut current_balance = total_amount;
+    let mut amortization_curve = Vec::with_capacity(total_months as usize);
+
+    for month in 0..total_months {
+        let installment = first_payment - step * Decimal::from(month);
+        let interest_payment = current_balance * monthly_interest_rate;
+        let amortization = if month == total_months - 1 {
+            current_balance
+        } else {
+            installment - interest_payment
+        };
+        if amortization < dec!(0) {
+            return Err(infeasible());
+        }
+
+        current_balance -= amortization;
+        amortization_curve.push(
+            MonthPayment {
+                new_balance: current_balance.max(dec!(0)),
+                current_amortization: amortization,
+                current_interest: interest_payment,
+                accrued_interest: dec!(0),
+                admin_fee: dec!(0),
+                insurance: dec!(0),
+                principal_paid_to_date: total_amount - current_balance,
+                present_value: None,
+                applied_rate: None,
+                employer_subsidy: dec!(0),
+            }
+        );
+        #[cfg(feature = "tracing")]
+        trace_month(0, &amortization_curve);
+    }
+
+    Ok(amortization_curve)
+}
+
+/// Calculates the number of Price installments needed to repay a loan with a given
+/// payment.
+///
+/// Uses the closed form `n = -ln(1 - P*i/PMT) / ln(1 + i)`, rounded up to a whole month
+/// (the last installment may then be smaller). For a zero rate, `n = P / PMT` rounded up.
+///
+/// # Errors
+///
+/// Returns an error if the payment does not exceed the first month's interest
+/// (`PMT <= P*i`), since the loan would never be repaid.
+pub fn price_term_for_payment(
+    total_amount: Decimal,
+    monthly_interest_rate: Decimal,
+    payment: Decimal,
+) -> Result<u32, FinancingError> {
+    use rust_decimal::prelude::ToPrimitive;
+
+    if payment <= total_amount * monthly_interest_rate || payment <= dec!(0) {
+        return Err(FinancingError::InvalidArgument(
+            "Payment must exceed the monthly interest on the principal.".to_string(),
+        ));
+    }
+
+    let months = if monthly_interest_rate.is_zero() {
+        total_amount / payment
+    } else {
+        -(dec!(1) - total_amount * monthly_interest_rate / payment).ln() / (dec!(1) + monthly_interest_rate).ln()
+    };
+
+    // Round away the noise of the logarithms before taking the ceiling.
+    months
+        .round_dp(8)
+        .ceil()
+        .to_u32()
+        .ok_or_else(|| FinancingError::InvalidArgument("Resulting term does not fit in u32.".to_string()))
+}
+
+/// Non-fatal conditions detected while building a schedule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CalculationWarning {
+    /// The installment of `month` (1-based) did not cover its interest, so the balance grew.
+    NegativeAmortization {
+        /// The month (1-based) in which the balance grew.
+        month: u32,
+    },
+}
+
+/// Contains the results of a Price loan whose installment is fixed while the rate floats.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct FloatingPriceResult {
+    /// The installment, fixed at origination from the first month's rate.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub fixed_payment: Decimal,
+    /// The total amount paid over the lifetime of the loan, including the final settlement.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub total_paid: Decimal,
+    /// A vector containing the payment details for each month.
+    pub amortization_curve: Vec<MonthPayment>,
+    /// Warnings raised while building the schedule, such as negative amortization.
+    pub warnings: Vec<CalculationWarning>,
+}
+
+/// Calculates a Price schedule with a fixed installment and a floating monthly rate.
+///
+/// The installment is computed at origination from the first rate of the path and kept
+/// for the whole term, while month `k` (zero-based) is charged `rate_path[k]` (a shorter
+/// path is padded with its last rate). When the rate rises enough for the interest to
+/// exceed the installment, the amortization is negative and the balance grows; such months
+/// are reported as `CalculationWarning::NegativeAmortization`. The final installment
+/// settles whatever balance remains.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero or `rate_path` is empty.
+pub fn calculate_floating_price(
+    total_amount: Decimal,
+    total_months: u32,
+    rate_path: &[Decimal],
+) -> Result<FloatingPriceResult, FinancingError> {
+    if total_months == 0 {
+        return Err(FinancingError::ZeroMonths);
+    }
+    let Some(last_rate) = rate_path.last() else {
+        return Err(FinancingError::InvalidArgument("Rate path cannot be empty.".to_string()));
+    };
+
+    let fixed_payment = price_fixed_payment(total_amount, rate_path[0], total_months);
+    let mut current_balance = total_amount;
+    let mut total_paid = dec!(0);
+    let mut amortization_curve = Vec::with_capacity(total_months as usize);
+    let mut warnings = Vec::new();
+
+    for month in 0..total_months {
+        let rate = *rate_path.get(month as usize).unwrap_or(last_rate);
+        let interest_payment = current_balance * rate;
+        let amortization = if month == total_months - 1 {
+            current_balance
+        } else {
+            fixed_payment - interest_payment
+        };
+        if amortization < dec!(0) {
+            warnings.push(CalculationWarning::NegativeAmortization { month: month + 1 });
+        }
+
+        current_balance -= amortization;
+        total_paid += amortization + interest_payment;
+        amortization_curve.push(
+            MonthPayment {
+                new_balance: current_balance,
+                current_amortization: amortization,
+                current_interest: interest_payment,
+                accrued_interest: dec!(0),
+                admin_fee: dec!(0),
+                insurance: dec!(0),
+                principal_paid_to_date: total_amount - current_balance,
+                present_value: None,
+                applied_rate: None,
+                employer_subsidy: dec!(0),
+            }
+        );
+        #[cfg(feature = "tracing")]
+        trace_month(0, &amortization_curve);
+    }
+
+    Ok(FloatingPriceResult {
+        fixed_payment: fixed_payment.round_dp(2),
+        total_paid: total_paid.round_dp(2),
+        amortization_curve,
+        warnings,
+    })
+}
+
+/// Formats a value as Brazilian reais, e.g. `R$ 1.062,74`.
+///
+/// The value is rounded to cents, with `.` as the thousands separator and `,` as the
+/// decimal separator. Negative values are prefixed with `-`.
+pub fn format_brl(value: Decimal) -> String {
+    let rounded = value.round_dp(2);
+    let text = format!("{:.2}", rounded.abs());
+    let (integer, cents) = text.split_once('.').unwrap_or((&text, "00"));
+
+    let mut grouped = String::new();
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            grouped.push('.');
+        }
+        grouped.push(digit);
+    }
+
+    let sign = if rounded.is_sign_negative() && !rounded.is_zero() { "-" } else { "" };
+    format!("{}R$ {},{}", sign, grouped, cents)
+}
+
+/// Renders a curve as a GitHub-flavored Markdown table.
+///
+/// When `edge_months` is set and the curve is longer than twice that, only the first and
+/// last `edge_months` rows are rendered, separated by an ellipsis row.
+fn markdown_table(amortization_curve: &[MonthPayment], edge_months: Option<usize>) -> String {
+    let mut markdown = String::from("| Month | Installment | Amortization | Interest | Balance |\n");
+    markdown.push_str("|---:|---:|---:|---:|---:|\n");
+
+    let row = |month: usize, payment: &MonthPayment| {
+        format!(
+            "| {} | {} | {} | {} | {} |\n",
+            month + 1,
+            format_brl(payment.installment()),
+            format_brl(payment.current_amortization),
+            format_brl(payment.current_interest),
+            format_brl(payment.new_balance),
+        )
+    };
+
+    let length = amortization_curve.len();
+    for (month, payment) in amortization_curve.iter().enumerate() {
+        match edge_months {
+            Some(edge) if length > 2 * edge && month >= edge && month < length - edge => {
+                if month == edge {
+                    markdown.push_str("| … | … | … | … | … |\n");
+                }
+            }
+            _ => markdown.push_str(&row(month, payment)),
+        }
+    }
+
+    markdown
+}
+
+/// Renders a curve as one `month installment amortization interest balance` line per
+/// month, amounts with two decimal places.
+fn snapshot_curve(amortization_curve: &[MonthPayment]) -> String {
+    let mut snapshot = String::from("month installment amortization interest balance\n");
+    for (month, payment) in amortization_curve.iter().enumerate() {
+        snapshot.push_str(&format!(
+            "{} {:.2} {:.2} {:.2} {:.2}\n",
+            month + 1,
+            payment.installment(),
+            payment.current_amortization,
+            payment.current_interest,
+            payment.new_balance,
+        ));
+    }
+
+    snapshot
+}
+
+impl PriceTableResult {
+    /// Renders the schedule as a Markdown table with BRL-formatted cells.
+    pub fn to_markdown(&self) -> String {
+        markdown_table(&self.amortization_curve, None)
+    }
+
+    /// Renders only the first and last `edge_months` months as a Markdown table, with an
+    /// ellipsis row in between for long terms.
+    pub fn to_markdown_truncated(&self, edge_months: usize) -> String {
+        markdown_table(&self.amortization_curve, Some(edge_months))
+    }
+
+    /// Renders a stable, line-oriented dump of every scalar field and the full curve, for
+    /// snapshot tests. Amounts use two decimal places.
+    pub fn to_snapshot_string(&self) -> String {
+        let mut snapshot = format!(
+            "fixed_payment: {:.2}\ntotal_paid: {:.2}\ntotal_interest: {:.2}\nsettled_at_month: {:?}\n\
+             interest_saved: {:.2}\nupfront_insurance: {:.2}\n",
+            self.fixed_payment,
+            self.total_paid,
+            self.total_interest,
+            self.settled_at_month,
+            self.interest_saved,
+            self.upfront_insurance,
+        );
+        snapshot.push_str(&snapshot_curve(&self.amortization_curve));
+        snapshot
+    }
+}
+
+impl SacTableResult {
+    /// Renders the schedule as a Markdown table with BRL-formatted cells.
+    pub fn to_markdown(&self) -> String {
+        markdown_table(&self.amortization_curve, None)
+    }
+
+    /// Renders only the first and last `edge_months` months as a Markdown table, with an
+    /// ellipsis row in between for long terms.
+    pub fn to_markdown_truncated(&self, edge_months: usize) -> String {
+        markdown_table(&self.amortization_curve, Some(edge_months))
+    }
+
+    /// Renders a stable, line-oriented dump of every scalar field and the full curve, for
+    /// snapshot tests. Amounts use two decimal places.
+    pub fn to_snapshot_string(&self) -> String {
+        let mut snapshot = format!(
+            "fixed_amortization: {:.2}\nfirst_payment: {:.2}\nlast_payment: {:.2}\ntotal_paid: {:.2}\n\
+             total_interest: {:.2}\nsettled_at_month: {:?}\ninterest_saved: {:.2}\nupfront_insurance: {:.2}\n",
+            self.fixed_amortization,
+            self.first_payment,
+            self.last_payment,
+            self.total_paid,
+            self.total_interest,
+            self.settled_at_month,
+            self.interest_saved,
+            self.upfront_insurance,
+        );
+        snapshot.push_str(&snapshot_curve(&self.amortization_curve));
+        snapshot
+    }
+}
+
+/// Calculates the principal-weighted monthly rate of loans stacked on one property.
+///
+/// Each tranche is an `(amount, monthly_rate)` pair, e.g. an SFH portion and an SFI
+/// portion above the SFH ceiling. Returns zero when the total amount is zero.
+pub fn blended_rate(tranches: &[(Decimal, Decimal)]) -> Decimal {
+    let total_amount: Decimal = tranches.iter().map(|(amount, _)| *amount).sum();
+    if total_amount.is_zero() {
+        return dec!(0);
+    }
+
+    tranches.iter().map(|(amount, rate)| amount * rate).sum::<Decimal>() / total_amount
+}
+
+/// A yearly snapshot of an amortization curve.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct YearlyPoint {
+    /// The loan year (1-based) that ends at this snapshot.
+    pub year: u32,
+    /// The month (1-based) of the snapshot, the last month of the year or of the loan.
+    pub month: u32,
+    /// The remaining balance at the end of the year.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub ending_balance: Decimal,
+    /// The interest paid from the start of the loan to the end of the year.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub cumulative_interest: Decimal,
+    /// The principal amortized from the start of the loan to the end of the year.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub cumulative_amortization: Decimal,
+}
+
+/// Resamples an amortization curve to one point per 12-month boundary.
+///
+/// The cumulative amortization is read from `principal_paid_to_date` and the cumulative
+/// interest is folded on the fly. A final partial year yields a last point at the end of
+/// the loan.
+pub fn yearly_samples(amortization_curve: &[MonthPayment]) -> Vec<YearlyPoint> {
+    let mut cumulative_interest = dec!(0);
+    let mut points = Vec::with_capacity(amortization_curve.len().div_ceil(12));
+
+    for (index, payment) in amortization_curve.iter().enumerate() {
+        cumulative_interest += payment.current_interest + payment.accrued_interest;
+        let month = index as u32 + 1;
+        if month.is_multiple_of(12) || index == amortization_curve.len() - 1 {
+            points.push(YearlyPoint {
+                year: month.div_ceil(12),
+                month,
+                ending_balance: payment.new_balance,
+                cumulative_interest,
+                cumulative_amortization: payment.principal_paid_to_date,
+            });
+        }
+    }
+
+    points
+}
+
+/// Minimum, maximum and mean of one metric across a portfolio.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct MetricStats {
+    /// The smallest value.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub min: Decimal,
+    /// The largest value.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub max: Decimal,
+    /// The arithmetic mean, rounded to cents.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub mean: Decimal,
+}
+
+impl MetricStats {
+    fn from_values(values: &[Decimal]) -> Self {
+        if values.is_empty() {
+            return MetricStats::default();
+        }
+
+        let sum: Decimal = values.iter().sum();
+        MetricStats {
+            min: values.iter().copied().fold(values[0], Decimal::min),
+            max: values.iter().copied().fold(values[0], Decimal::max),
+            mean: (sum / Decimal::from(values.len())).round_dp(2),
+        }
+    }
+}
+
+/// Aggregated metrics of one amortization system across a portfolio.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct SystemStats {
+    /// Statistics of the total paid per loan.
+    pub total_paid: MetricStats,
+    /// Statistics of the total interest paid per loan.
+    pub total_interest: MetricStats,
+    /// Statistics of the first installment per loan.
+    pub first_installment: MetricStats,
+}
+
+impl SystemStats {
+    fn from_curves<'a>(totals: &[Decimal], curves: impl Iterator<Item = &'a [MonthPayment]>) -> Self {
+        let (interest, first): (Vec<Decimal>, Vec<Decimal>) = curves
+            .map(|curve| {
+                let first = curve.first().map(MonthPayment::installment).unwrap_or_default();
+                (total_interest(curve).round_dp(2), first.round_dp(2))
+            })
+            .unzip();
+
+        SystemStats {
+            total_paid: MetricStats::from_values(totals),
+            total_interest: MetricStats::from_values(&interest),
+            first_installment: MetricStats::from_values(&first),
+        }
+    }
+}
+
+/// Aggregated statistics of a portfolio of loans, per amortization system.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct PortfolioStats {
+    /// The number of loans aggregated.
+    pub loan_count: usize,
+    /// Statistics of the SAC tables.
+    pub sac: SystemStats,
+    /// Statistics of the Price tables.
+    pub price: SystemStats,
+}
+
+/// Aggregates many single-loan results into portfolio statistics.
+///
+/// An empty slice yields zeroed statistics.
+pub fn aggregate(results: &[DebtTrajectoryResult]) -> PortfolioStats {
+    let sac_totals: Vec<Decimal> = results.iter().map(|result| result.sac_table.total_paid).collect();
+    let price_totals: Vec<Decimal> = results.iter().map(|result| result.price_table.total_paid).collect();
+
+    PortfolioStats {
+        loan_count: results.len(),
+        sac: SystemStats::from_curves(
+            &sac_totals,
+            results.iter().map(|result| result.sac_table.amortization_curve.as_slice()),
+        ),
+        price: SystemStats::from_curves(
+            &price_totals,
+            results.iter().map(|result| result.price_table.amortization_curve.as_slice()),
+        ),
+    }
+}
+
+/// Calculates a schedule whose rate resets quarterly from a reference index.
+///
+/// The annual rate of quarter `q` (0-based, starting at month `3q + 1`) is
+/// `index_per_quarter[q] + spread_per_year`, both as percentages; the last index value
+/// carries forward if the series is shorter than the term. The rate is fixed within a
+/// quarter. As in `calculate_with_rate_steps`, Price recomputes the installment at each
+/// reset and SAC keeps a constant amortization. `step_installments` lists the rate used
+/// in each quarter. Each reset rate is clamped by `collar`, so a capped floating loan
+/// never charges more than its ceiling or less than its floor.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero, if the index series is empty, if any
+/// resulting rate is at or below -100%, or if the floor is above the ceiling.
+pub fn calculate_quarterly_index_resets(
+    total_amount: Decimal,
+    total_months: u32,
+    index_per_quarter: &[Decimal],
+    spread_per_year: Decimal,
+    collar: RateCollar,
+    system: AmortizationSystem,
+) -> Result<SteppedRateResult, FinancingError> {
+    let Some(last_index) = index_per_quarter.last() else {
+        return Err(FinancingError::InvalidArgument("Index series cannot be empty.".to_string()));
+    };
+
+    let steps: Vec<(u32, Decimal)> = (0..total_months.div_ceil(3))
+        .map(|quarter| {
+            let index = index_per_quarter.get(quarter as usize).unwrap_or(last_index);
+            (quarter * 3 + 1, *index + spread_per_year)
+        })
+        .collect();
+
+    calculate_with_rate_steps(total_amount, total_months, &steps, collar, system)
+}
+
+/// The criterion a borrower uses to choose between SAC and Price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BorrowerPreference {
+    /// The smallest first installment.
+    LowestFirstPayment,
+    /// The smallest total paid over the term.
+    LowestTotalPaid,
+    /// The fastest principal repayment, measured as the cumulative principal paid to date
+    /// summed over every month.
+    FastestEquity,
+}
+
+/// Computes both tables and picks the system matching the borrower's preference.
+///
+/// Ties are resolved in favor of SAC.
+///
+/// # Errors
+///
+/// Returns an error if the `total_months` is zero or the interest rate is at or below -100%.
+pub fn recommend_system(
+    input: DebtCalculationInput,
+    preference: BorrowerPreference,
+) -> Result<(AmortizationSystem, DebtTrajectoryResult), FinancingError> {
+    let result = calculate(&LoanConfig::from(input))?;
+    let equity = |curve: &[MonthPayment]| curve.iter().map(|month| month.principal_paid_to_date).sum::<Decimal>();
+
+    let sac_wins = match preference {
+        BorrowerPreference::LowestFirstPayment => {
+            let first = |curve: &[MonthPayment]| curve.first().map(MonthPayment::installment).unwrap_or_default();
+            first(&result.sac_table.amortization_curve) <= first(&result.price_table.amortization_curve)
+        }
+        BorrowerPreference::LowestTotalPaid => result.sac_table.total_paid <= result.price_table.total_paid,
+        BorrowerPreference::FastestEquity => {
+            equity(&result.sac_table.amortization_curve) >= equity(&result.price_table.amortization_curve)
+        }
+    };
+    let system = if sac_wins { AmortizationSystem::Sac } else { AmortizationSystem::Price };
+
+    Ok((system, result))
+}
+
+/// Back-solves the original principal from the outstanding balance at a given month.
+///
+/// The balance after `month` installments is proportional to the principal in both
+/// systems: `P(1 - k/n)` in SAC and `P[(1 + i)^n - (1 + i)^k] / [(1 + i)^n - 1]` in
+/// Price, so the principal is the known balance divided by that factor. The result is
+/// rounded to cents.
+///
+/// # Arguments
+///
+/// * `known_balance_at_month` - The outstanding balance right after installment `month`.
+/// * `month` - The number of installments already paid.
+/// * `monthly_rate` - The effective monthly interest rate as a decimal (not percentage).
+/// * `total_months` - The total number of payments.
+/// * `system` - The amortization system of the contract.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero, or if the balance is negative or no
+/// principal is consistent with it (e.g. `month` is at or past the end of the term).
+pub fn infer_principal_from_balance(
+    known_balance_at_month: Decimal,
+    month: u32,
+    monthly_rate: Decimal,
+    total_months: u32,
+    system: AmortizationSystem,
+) -> Result<Decimal, FinancingError> {
+    if total_months == 0 {
+        return Err(FinancingError::ZeroMonths);
+    }
+    if known_balance_at_month < dec!(0) {
+        return Err(FinancingError::InvalidArgument("Outstanding balance cannot be negative.".to_string()));
+    }
+    if month >= total_months {
+        return Err(FinancingError::InvalidArgument(
+            "No principal is consistent with a balance at or after the last month.".to_string(),
+        ));
+    }
+
+    let remaining_share = match system {
+        AmortizationSystem::Price if !monthly_rate.is_zero() => {
+            let full = (dec!(1) + monthly_rate).powu(total_months.into());
+            let elapsed = (dec!(1) + monthly_rate).powu(month.into());
+            (full - elapsed) / (full - dec!(1))
+        }
+        _ => Decimal::from(total_months - month) / Decimal::from(total_months),
+    };
+
+    Ok((known_balance_at_month / remaining_share).round_dp(2))
+}
+
+/// Calculates the CET (Custo Efetivo Total) of a loan with upfront fees.
+///
+/// The CET is the annualized internal rate of return of the borrower's real cash flows:
+/// at time zero the borrower receives the net amount, and at the end of each month pays
+/// the installment. The upfront fees enter the solve in one of two ways:
+///
+/// * Paid at signing (`fees_financed = false`): the schedule is built on `total_amount`
+///   and the borrower receives `total_amount - upfront_fees`.
+/// * Rolled into the principal (`fees_financed = true`): the schedule is built on
+///   `total_amount + upfront_fees`, which also accrues interest, and the borrower
+///   receives `total_amount`.
+///
+/// The monthly rate is found by bisection between 0% and 100% and annualized as
+/// `(1 + i)^12 - 1`. The result is a decimal (0.12 for 12%), not a percentage.
+///
+/// # Arguments
+///
+/// * `total_amount` - The amount of credit the borrower takes.
+/// * `monthly_interest_rate` - The contract's monthly interest rate as a decimal.
+/// * `total_months` - The total number of payments.
+/// * `system` - The amortization system of the schedule.
+/// * `upfront_fees` - The fees charged at signing (tarifas, IOF).
+/// * `fees_financed` - Whether the fees are added to the principal instead of paid.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero, if the fees paid at signing consume the
+/// whole amount, or if no rate between 0% and 100% a month matches the cash flows.
+pub fn calculate_cet(
+    total_amount: Decimal,
+    monthly_interest_rate: Decimal,
+    total_months: u32,
+    system: AmortizationSystem,
+    upfront_fees: Decimal,
+    fees_financed: bool,
+) -> Result<Decimal, FinancingError> {
+    let (principal, net_received) = if fees_financed {
+        (total_amount + upfront_fees, total_amount)
+    } else {
+        (total_amount, total_amount - upfront_fees)
+    };
+    if net_received <= dec!(0) {
+        return Err(FinancingError::InvalidArgument("Upfront fees consume the whole loan.".to_string()));
+    }
+
+    let amortization_curve = match system {
+        AmortizationSystem::Price => build_price_table(principal, monthly_interest_rate.into(), total_months)?.amortization_curve,
+        AmortizationSystem::Sac => build_sac_table(principal, monthly_interest_rate.into(), total_months)?.amortization_curve,
+    };
+    let installments: Vec<Decimal> = amortization_curve.iter().map(MonthPayment::installment).collect();
+
+    let monthly_cet = bisect(|rate| npv(&installments, rate) - net_received, dec!(0), dec!(1))
+        .ok_or_else(|| FinancingError::InvalidArgument("No CET matches the cash flows.".to_string()))?;
+
+    Ok((dec!(1) + monthly_cet).powu(12) - dec!(1))
+}
+
+/// The results of a consórcio plan schedule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct ConsorcioResult {
+    /// The total amount paid over the plan, including the administration fee.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub total_paid: Decimal,
+    /// The total administration fee (taxa de administração) paid over the plan.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub total_admin_fee: Decimal,
+    /// A vector containing the payment details for each month.
+    pub amortization_curve: Vec<MonthPayment>,
+}
+
+/// Calculates the schedule of a consórcio plan, which charges no interest.
+///
+/// Each installment is `total_amount / n` plus the administration fee spread evenly over
+/// the term, both adjusted by the accumulated index: month `k` (zero-based) multiplies the
+/// running factor by `1 + index_series[k]`. Months beyond the series are not adjusted,
+/// so an empty series yields a flat plan. The outstanding balance is the remaining
+/// credit at the current index value. The fee is reported in `admin_fee`.
+///
+/// # Arguments
+///
+/// * `total_amount` - The credit value (carta de crédito).
+/// * `admin_fee_total_percent` - The administration fee over the whole plan, as a
+///   percentage of the credit (e.g., 15 for 15%).
+/// * `total_months` - The total number of installments.
+/// * `index_series` - The monthly index variations as decimals (0.005 for 0.5%).
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero.
+pub fn calculate_consorcio(
+    total_amount: Decimal,
+    admin_fee_total_percent: Decimal,
+    total_months: u32,
+    index_series: &[Decimal],
+) -> Result<ConsorcioResult, FinancingError> {
+    if total_months == 0 {
+        return Err(FinancingError::ZeroMonths);
+    }
+
+    let months = Decimal::from(total_months);
+    let base_amortization = total_amount / months;
+    let base_admin_fee = total_amount * admin_fee_total_percent / dec!(100) / months;
+    let mut index_factor = dec!(1);
+    let mut principal_paid = dec!(0);
+    let mut amortization_curve = Vec::with_capacity(total_months as usize);
+
+    for month in 0..total_months {
+        index_factor *= dec!(1) + index_series.get(month as usize).copied().unwrap_or_default();
+        let amortization = base_amortization * index_factor;
+        principal_paid += amortization;
+        amortization_curve.push(
+            MonthPayment {
+                new_balance: base_amortization * Decimal::from(total_months - month - 1) * index_factor,
+                current_amortization: amortization,
+                current_interest: dec!(0),
+                accrued_interest: dec!(0),
+                admin_fee: base_admin_fee * index_factor,
+                insurance: dec!(0),
+                principal_paid_to_date: principal_paid,
+                present_value: None,
+                applied_rate: None,
+                employer_subsidy: dec!(0),
+            }
+        );
+        #[cfg(feature = "tracing")]
+        trace_month(0, &amortization_curve);
+    }
+
+    let total_paid = amortization_curve.iter().map(MonthPayment::installment).sum::<Decimal>().round_dp(2);
+    let total_admin_fee = amortization_curve.iter().map(|month| month.admin_fee).sum::<Decimal>().round_dp(2);
+
+    Ok(ConsorcioResult { total_paid, total_admin_fee, amortization_curve })
+}
+
+/// Returns the fraction of the principal amortized by the halfway month of a curve.
+///
+/// The midpoint is month `total_months / 2` (1-based), and the fraction is that month's
+/// `principal_paid_to_date` over the opening balance of the first month, so 0.5 means
+/// half the principal is paid. Returns zero for curves shorter than two months or with a
+/// zero principal.
+pub fn principal_paid_at_midpoint(amortization_curve: &[MonthPayment]) -> Decimal {
+    let midpoint = amortization_curve.len() / 2;
+    if midpoint == 0 {
+        return dec!(0);
+    }
+
+    let at_midpoint = &amortization_curve[midpoint - 1];
+
+    let principal = amortization_curve[0].opening_balance();
+    if principal.is_zero() {
+        return dec!(0);
+    }
+
+    at_midpoint.principal_paid_to_date / principal
+}
+
+/// Returns each installment of a curve net of the tax benefit of deductible interest.
+///
+/// Month `k` yields `installment - tax_rate * current_interest`, the after-tax cash cost
+/// of the month. Only the schedule interest is treated as deductible.
+///
+/// # Arguments
+///
+/// * `amortization_curve` - The schedule to transform.
+/// * `tax_rate` - The marginal tax rate as a decimal (0.34 for 34%).
+pub fn after_tax_schedule(amortization_curve: &[MonthPayment], tax_rate: Decimal) -> Vec<Decimal> {
+    amortization_curve
+        .iter()
+        .map(|payment| payment.installment() - tax_rate * payment.current_interest)
+        .collect()
+}
+
+/// Recomputes a schedule after a lump-sum prepayment.
+///
+/// The prepayment is applied right after the installment of `at_month` (1-based) is paid;
+/// earlier rows are kept as they are. Each later month is charged the rate realized by
+/// the same month of the original curve, and keeps its fees and accrued interest.
+///
+/// * `PrepaymentStrategy::ReduceTerm` keeps the next installment (Price) or amortization
+///   (SAC) and pays the loan off earlier.
+/// * `PrepaymentStrategy::ReduceInstallment` keeps the remaining term and recomputes the
+///   installment (Price) or amortization (SAC) from the reduced balance.
+///
+/// A prepayment at or above the outstanding balance settles the loan at `at_month`.
+///
+/// # Errors
+///
+/// Returns an error if `at_month` does not leave at least one remaining installment, or
+/// if the prepayment is negative.
+pub fn apply_prepayment(
+    amortization_curve: &[MonthPayment],
+    at_month: u32,
+    prepayment: Decimal,
+    strategy: PrepaymentStrategy,
+    system: AmortizationSystem,
+) -> Result<Vec<MonthPayment>, FinancingError> {
+    let at_month = at_month as usize;
+    if at_month == 0 || at_month >= amortization_curve.len() {
+        return Err(FinancingError::InvalidArgument(
+            "Prepayment month must leave at least one remaining installment.".to_string(),
+        ));
+    }
+    if prepayment < dec!(0) {
+        return Err(FinancingError::InvalidArgument("Prepayment cannot be negative.".to_string()));
+    }
+
+    let total_amount = amortization_curve[0].opening_balance();
+    let next = &amortization_curve[at_month];
+    let remaining_months = (amortization_curve.len() - at_month) as u32;
+    let mut current_balance = (amortization_curve[at_month - 1].new_balance - prepayment).max(dec!(0));
+    let fixed_value = match (strategy, system) {
+        (PrepaymentStrategy::ReduceTerm, AmortizationSystem::Price) => next.current_amortization + next.current_interest,
+        (PrepaymentStrategy::ReduceTerm, AmortizationSystem::Sac) => next.current_amortization,
+        (PrepaymentStrategy::ReduceInstallment, AmortizationSystem::Price) => {
+            price_fixed_payment(current_balance, next.realized_rate(), remaining_months)
+        }
+        (PrepaymentStrategy::ReduceInstallment, AmortizationSystem::Sac) => {
+            current_balance / Decimal::from(remaining_months)
+        }
+    };
+
+    let mut amortization_curve_after = amortization_curve[..at_month].to_vec();
+    if let Some(last) = amortization_curve_after.last_mut() {
+        last.new_balance = current_balance;
+        last.principal_paid_to_date = total_amount - current_balance;
+    }
+
+    for (index, original) in amortization_curve.iter().enumerate().skip(at_month) {
+        if current_balance <= dec!(0) {
+            break;
+        }
+
+        let interest_payment = current_balance * original.realized_rate();
+        let amortization = if index == amortization_curve.len() - 1 {
+            current_balance
+        } else {
+            match system {
+                AmortizationSystem::Price => fixed_value - interest_payment,
+                AmortizationSystem::Sac => fixed_value,
+            }
+            .min(current_balance)
+        };
+
+        current_balance -= amortization;
+        amortization_curve_after.push(
+            MonthPayment {
+                new_balance: current_balance.max(dec!(0)),
+                current_amortization: amortization,
+                current_interest: interest_payment,
+                accrued_interest: original.accrued_interest,
+                admin_fee: original.admin_fee,
+                insurance: original.insurance,
+                principal_paid_to_date: total_amount - current_balance,
+                present_value: None,
+                applied_rate: None,
+                employer_subsidy: dec!(0),
+            }
+        );
+    }
+
+    Ok(amortization_curve_after)
+}
+
+/// Finds the month at which a lump sum saves the most interest, and how much it saves.
+///
+/// Every month from `available_from_month` (1-based) to the second-to-last one is tried
+/// with `apply_prepayment`; the earliest month with the largest saving wins. Since a
+/// prepayment saves more the earlier it is made, this is normally `available_from_month`
+/// itself, and the saving is the best achievable when the sum only becomes available
+/// later in the term.
+///
+/// # Errors
+///
+/// Returns an error if `available_from_month` does not leave at least one remaining
+/// installment, or if the lump sum is negative.
+pub fn best_prepayment_month(
+    amortization_curve: &[MonthPayment],
+    lump_sum: Decimal,
+    available_from_month: u32,
+    strategy: PrepaymentStrategy,
+    system: AmortizationSystem,
+) -> Result<(u32, Decimal), FinancingError> {
+    let saving_at = |month: u32| {
+        apply_prepayment(amortization_curve, month, lump_sum, strategy, system)
+            .map(|after| interest_saved_by_prepayment(amortization_curve, &after).round_dp(2))
+    };
+
+    let mut best = (available_from_month, saving_at(available_from_month)?);
+    for month in available_from_month + 1..amortization_curve.len() as u32 {
+        let saved = saving_at(month)?;
+        if saved > best.1 {
+            best = (month, saved);
+        }
+    }
+
+    Ok(best)
+}
+
+/// Recomputes a schedule after the installment of `at_month` (1-based) is missed.
+///
+/// The missed installment is charged a penalty (multa) of `penalty_rate` on its amount
+/// and late interest (juros de mora) of `daily_late_rate * days_late` on its amount. The
+/// month's row records nothing paid: its interest includes the penalty and late
+/// interest, and the whole of it is capitalized as negative amortization. The remaining
+/// months keep the term and are recomputed from the increased balance, as in
+/// `apply_prepayment` with `PrepaymentStrategy::ReduceInstallment`.
+///
+/// # Arguments
+///
+/// * `amortization_curve` - The schedule before the missed payment.
+/// * `at_month` - The month (1-based) whose installment is missed.
+/// * `penalty_rate` - The penalty as a decimal of the installment (0.02 for 2%).
+/// * `daily_late_rate` - The late interest per day as a decimal (0.000333 for 1% a month).
+/// * `days_late` - The number of days the installment stays overdue.
+/// * `system` - The amortization system of the schedule.
+///
+/// # Errors
+///
+/// Returns an error if `at_month` does not leave at least one remaining installment.
+pub fn apply_arrears(
+    amortization_curve: &[MonthPayment],
+    at_month: u32,
+    penalty_rate: Decimal,
+    daily_late_rate: Decimal,
+    days_late: u32,
+    system: AmortizationSystem,
+) -> Result<Vec<MonthPayment>, FinancingError> {
+    let index = at_month as usize;
+    if index == 0 || index >= amortization_curve.len() {
+        return Err(FinancingError::InvalidArgument(
+            "Missed month must leave at least one remaining installment.".to_string(),
+        ));
+    }
+
+    let total_amount = amortization_curve[0].opening_balance();
+    let missed = &amortization_curve[index - 1];
+    let installment = missed.current_amortization + missed.current_interest;
+    let charges = installment * (penalty_rate + daily_late_rate * Decimal::from(days_late));
+    let unpaid_interest = missed.current_interest + charges;
+    let new_balance = missed.opening_balance() + unpaid_interest;
+
+    let mut with_arrears = amortization_curve.to_vec();
+    with_arrears[index - 1] = MonthPayment {
+        new_balance,
+        current_amortization: -unpaid_interest,
+        current_interest: unpaid_interest,
+        accrued_interest: dec!(0),
+        admin_fee: dec!(0),
+        insurance: dec!(0),
+        principal_paid_to_date: total_amount - new_balance,
+        present_value: None,
+        applied_rate: None,
+        employer_subsidy: dec!(0),
+    };
+
+    apply_prepayment(&with_arrears, at_month, dec!(0), PrepaymentStrategy::ReduceInstallment, system)
+}
+
+/// Splits an installment paid on a balance into its amortization and interest.
+///
+/// The interest is `balance * monthly_rate` and the amortization is the rest of the
+/// installment, which is negative when the installment does not cover the interest
+/// (negative amortization).
+///
+/// # Arguments
+///
+/// * `balance` - The outstanding balance before the installment.
+/// * `monthly_rate` - The monthly interest rate as a decimal (not percentage).
+/// * `installment` - The amount paid.
+pub fn split_installment(balance: Decimal, monthly_rate: Decimal, installment: Decimal) -> (Decimal, Decimal) {
+    let interest = balance * monthly_rate;
+    (installment - interest, interest)
+}
+
+/// Calculates the additional total paid for each extra year added to a term.
+///
+/// Entry `y` (zero-based) is the total paid with `base_months + 12(y + 1)` months minus
+/// the total paid with `base_months + 12y` months, so the first entry is the cost of the
+/// first extra year.
+///
+/// # Arguments
+///
+/// * `total_amount` - The principal loan amount.
+/// * `annual_rate` - The annual interest rate as a percentage (e.g., 10.5 for 10.5%).
+/// * `base_months` - The term the extra years are added to.
+/// * `extra_years` - How many years to add, one at a time.
+/// * `system` - The amortization system used to build the schedules.
+///
+/// # Errors
+///
+/// Returns an error if `base_months` is zero or the rate is at or below -100%.
+pub fn marginal_cost_per_year(
+    total_amount: Decimal,
+    annual_rate: Decimal,
+    base_months: u32,
+    extra_years: u32,
+    system: AmortizationSystem,
+) -> Result<Vec<Decimal>, FinancingError> {
+    let context = RateContext::new(annual_rate)?;
+    let total_paid = |months: u32| match system {
+        AmortizationSystem::Price => context.price_table(total_amount, months).map(|table| table.total_paid),
+        AmortizationSystem::Sac => context.sac_table(total_amount, months).map(|table| table.total_paid),
+    };
+
+    let mut previous = total_paid(base_months)?;
+    (1..=extra_years)
+        .map(|year| {
+            let current = total_paid(base_months + 12 * year)?;
+            let marginal = current - previous;
+            previous = current;
+            Ok(marginal)
+        })
+        .collect()
+}
+
+/// Finds the fixed rate whose schedule has the same total paid as an indexed schedule.
+///
+/// The fixed monthly rate is found by bisection between 0% and 100% a month, comparing
+/// the indexed curve's summed installments with the fixed schedule's total paid: `n PMT`
+/// in Price and `P + iP(n + 1) / 2` in SAC. The result is annualized as `(1 + i)^12 - 1`
+/// and is a decimal (0.12 for 12%), not a percentage.
+///
+/// # Arguments
+///
+/// * `indexed_curve` - The schedule of the indexed loan (e.g. TR or IPCA).
+/// * `total_amount` - The principal loan amount.
+/// * `total_months` - The total number of payments.
+/// * `system` - The amortization system of the fixed-rate schedule.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero, or if no rate between 0% and 100% a month
+/// reproduces the indexed total.
+pub fn equivalent_fixed_rate(
+    indexed_curve: &[MonthPayment],
+    total_amount: Decimal,
+    total_months: u32,
+    system: AmortizationSystem,
+) -> Result<Decimal, FinancingError> {
+    if total_months == 0 {
+        return Err(FinancingError::ZeroMonths);
+    }
+
+    let indexed_total: Decimal = indexed_curve.iter().map(MonthPayment::installment).sum();
+    let months = Decimal::from(total_months);
+    let unit_installments = vec![dec!(1); total_months as usize];
+    let fixed_total = |rate: Decimal| match system {
+        // `P / npv(1, ..., 1)` is the Price installment without the `(1 + i)^n` overflow.
+        AmortizationSystem::Price => total_amount / npv(&unit_installments, rate) * months,
+        AmortizationSystem::Sac => total_amount + rate * total_amount * (months + dec!(1)) / dec!(2),
+    };
+
+    let monthly_rate = bisect(|rate| fixed_total(rate) - indexed_total, dec!(0), dec!(1)).ok_or_else(|| {
+        FinancingError::InvalidArgument("No fixed rate reproduces the indexed total.".to_string())
+    })?;
+
+    Ok((dec!(1) + monthly_rate).powu(12) - dec!(1))
+}
+
+/// Calculates the loan-to-value ratio over the life of a curve.
+///
+/// The first entry is the origination LTV, the first month's opening balance over
+/// `property_value`; entry `k` is month `k`'s `new_balance` over the property value
+/// appreciated for `k` months, `property_value * (1 + appreciation_rate)^k`. Returns an
+/// empty vector for an empty curve or a non-positive property value.
+///
+/// # Arguments
+///
+/// * `amortization_curve` - The schedule of the loan.
+/// * `property_value` - The appraised value of the property at origination.
+/// * `appreciation_rate` - The monthly appreciation as a decimal, zero to keep it flat.
+pub fn ltv_trajectory(
+    amortization_curve: &[MonthPayment],
+    property_value: Decimal,
+    appreciation_rate: Decimal,
+) -> Vec<Decimal> {
+    let Some(first) = amortization_curve.first() else {
+        return Vec::new();
+    };
+    if property_value <= dec!(0) {
+        return Vec::new();
+    }
+
+    let mut estimated_value = property_value;
+    let mut trajectory = Vec::with_capacity(amortization_curve.len() + 1);
+    trajectory.push(first.opening_balance() / property_value);
+    for payment in amortization_curve {
+        estimated_value *= dec!(1) + appreciation_rate;
+        trajectory.push(payment.new_balance / estimated_value);
+    }
+
+    trajectory
+}
+
+/// Returns the Price installment, `P * [i(1 + i)^n] / [(1 + i)^n – 1]`, without building
+/// a schedule. Returns zero when `total_months` is zero.
+pub fn price_installment(total_amount: Decimal, monthly_interest_rate: Decimal, total_months: u32) -> Decimal {
+    if total_months == 0 {
+        return dec!(0);
+    }
+
+    price_fixed_payment(total_amount, monthly_interest_rate, total_months)
+}
+
+/// Returns the first SAC installment, `P/n + P*i`, without building a schedule. Returns
+/// zero when `total_months` is zero.
+pub fn sac_first_installment(total_amount: Decimal, monthly_interest_rate: Decimal, total_months: u32) -> Decimal {
+    if total_months == 0 {
+        return dec!(0);
+    }
+
+    total_amount / Decimal::from(total_months) + total_amount * monthly_interest_rate
+}
+
+/// Returns the last SAC installment, `(P/n)(1 + i)`, without building a schedule. Returns
+/// zero when `total_months` is zero.
+pub fn sac_last_installment(total_amount: Decimal, monthly_interest_rate: Decimal, total_months: u32) -> Decimal {
+    if total_months == 0 {
+        return dec!(0);
+    }
+
+    total_amount / Decimal::from(total_months) * (dec!(1) + monthly_interest_rate)
+}
+
+/// Builds the SAC table consistent with a quoted first installment and term.
+///
+/// Since the first SAC installment is `P/n + P*i`, the monthly rate is
+/// `(first_payment - P/n) / P`, and the table is then calculated with it.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero, if `total_amount` is not positive, or if
+/// `first_payment` is below the pure-amortization floor `P/n`.
+pub fn sac_table_from_first_payment(
+    total_amount: Decimal,
+    first_payment: Decimal,
+    total_months: u32,
+) -> Result<SacTableResult, FinancingError> {
+    if total_months == 0 {
+        return Err(FinancingError::ZeroMonths);
+    }
+    if total_amount <= dec!(0) {
+        return Err(FinancingError::InvalidArgument("Total amount must be positive.".to_string()));
+    }
+
+    let fixed_amortization = total_amount / Decimal::from(total_months);
+    if first_payment < fixed_amortization {
+        return Err(FinancingError::InvalidArgument(
+            "First payment is below the pure-amortization floor.".to_string(),
+        ));
+    }
+
+    let monthly_interest_rate = (first_payment - fixed_amortization) / total_amount;
+    build_sac_table(total_amount, monthly_interest_rate.into(), total_months)
+}
+
+/// Calculates the present value of the tax shield generated by deductible interest.
+///
+/// Each month's shield, `tax_rate * current_interest`, is discounted to time zero with
+/// `npv`, matching `after_tax_schedule`'s monthly benefit.
+///
+/// # Arguments
+///
+/// * `amortization_curve` - The schedule of the loan.
+/// * `tax_rate` - The marginal tax rate as a decimal (0.34 for 34%).
+/// * `discount_rate` - The monthly discount rate as a decimal (not percentage).
+pub fn tax_shield_pv(amortization_curve: &[MonthPayment], tax_rate: Decimal, discount_rate: Decimal) -> Decimal {
+    let shields: Vec<Decimal> = amortization_curve.iter().map(|payment| tax_rate * payment.current_interest).collect();
+
+    npv(&shields, discount_rate)
+}
+
+/// Checks the SAC invariant that the last installment is one amortization plus one
+/// month's interest on it: `fixed_amortization * (1 + monthly_rate)`.
+///
+/// The final outstanding balance of a SAC schedule is exactly one amortization, so its
+/// interest is `fixed_amortization * monthly_rate`. The comparison uses the unrounded
+/// amortization `P / n` and accepts a difference of up to one cent in the rounded
+/// `last_payment`. Returns false for an empty table.
+pub fn verify_sac_last_payment(table: &SacTableResult, monthly_rate: Decimal) -> bool {
+    let Some(first) = table.amortization_curve.first() else {
+        return false;
+    };
+
+    let months = table.amortization_curve.len() as u32;
+    let expected = sac_last_installment(first.opening_balance(), monthly_rate, months);
+    (table.last_payment - expected).abs() <= dec!(0.01)
+}
+
+/// A schedule whose term was checked against a policy maximum.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct CappedTermResult {
+    /// The term of the returned schedule.
+    pub total_months: u32,
+    /// Whether the natural term exceeded the maximum and the term was capped, raising the
+    /// installment above the desired one.
+    pub capped: bool,
+    /// The first installment of the returned schedule.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub first_installment: Decimal,
+    /// A vector containing the payment details for each month.
+    pub amortization_curve: Vec<MonthPayment>,
+}
+
+/// Builds the schedule for a desired installment, capping the term at a maximum.
+///
+/// The natural term is the one the desired installment repays: `price_term_for_payment`
+/// in Price, and `ceil(P / (PMT - P*i))` in SAC, where the desired installment is the
+/// first one. If it fits within `max_months` the schedule uses it; otherwise, including
+/// when the installment never repays the loan, the schedule uses `max_months` with the
+/// correspondingly higher installment and `capped` is set.
+///
+/// # Errors
+///
+/// Returns an error if `max_months` is zero.
+pub fn cap_term(
+    total_amount: Decimal,
+    monthly_interest_rate: Decimal,
+    desired_installment: Decimal,
+    max_months: u32,
+    system: AmortizationSystem,
+) -> Result<CappedTermResult, FinancingError> {
+    use rust_decimal::prelude::ToPrimitive;
+
+    if max_months == 0 {
+        return Err(FinancingError::ZeroMonths);
+    }
+
+    let amortization_share = desired_installment - total_amount * monthly_interest_rate;
+    let natural_months = match system {
+        AmortizationSystem::Price => price_term_for_payment(total_amount, monthly_interest_rate, desired_installment).ok(),
+        AmortizationSystem::Sac if amortization_share > dec!(0) => {
+            (total_amount / amortization_share).round_dp(8).ceil().to_u32()
+        }
+        AmortizationSystem::Sac => None,
+    };
+    let (total_months, capped) = match natural_months {
+        Some(months) if months <= max_months => (months.max(1), false),
+        _ => (max_months, true),
+    };
+
+    let amortization_curve = match system {
+        AmortizationSystem::Price => build_price_table(total_amount, monthly_interest_rate.into(), total_months)?.amortization_curve,
+        AmortizationSystem::Sac => build_sac_table(total_amount, monthly_interest_rate.into(), total_months)?.amortization_curve,
+    };
+    let first_installment = amortization_curve.first().map(MonthPayment::installment).unwrap_or_default().round_dp(2);
+
+    Ok(CappedTermResult { total_months, capped, first_installment, amortization_curve })
+}
+
+/// Finds the month at which paying points to lower the rate breaks even.
+///
+/// Both schedules are built over the same term, and the month-by-month differences
+/// between the installments without and with points are accumulated, as in
+/// `cumulative_cost_crossover`. The first month (1-based) in which the cumulative saving
+/// reaches `points_cost` is returned, or `None` if it never does within the term.
+///
+/// # Arguments
+///
+/// * `total_amount` - The principal loan amount.
+/// * `total_months` - The total number of payments.
+/// * `rate_without_points` - The monthly rate without points, as a decimal.
+/// * `rate_with_points` - The lower monthly rate obtained by paying points, as a decimal.
+/// * `points_cost` - The upfront cost of the points.
+/// * `system` - The amortization system of both schedules.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero.
+pub fn points_break_even(
+    total_amount: Decimal,
+    total_months: u32,
+    rate_without_points: Decimal,
+    rate_with_points: Decimal,
+    points_cost: Decimal,
+    system: AmortizationSystem,
+) -> Result<Option<u32>, FinancingError> {
+    let curve = |rate: Decimal| match system {
+        AmortizationSystem::Price => build_price_table(total_amount, rate.into(), total_months).map(|t| t.amortization_curve),
+        AmortizationSystem::Sac => build_sac_table(total_amount, rate.into(), total_months).map(|t| t.amortization_curve),
+    };
+    let without_points = curve(rate_without_points)?;
+    let with_points = curve(rate_with_points)?;
+
+    let mut saving = dec!(0);
+    for (month, (without, with)) in without_points.iter().zip(&with_points).enumerate() {
+        saving += without.installment() - with.installment();
+        if saving >= points_cost {
+            return Ok(Some(month as u32 + 1));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns only the balance after each month of a schedule.
+///
+/// It runs the same recurrence as `calculate_price_table` and `calculate_sac_table` but
+/// keeps a single `Decimal` per month, so the values match the `new_balance` field of
+/// the full schedule exactly. Returns an empty vector when `total_months` is zero.
+pub fn balance_curve(
+    total_amount: Decimal,
+    monthly_rate: Decimal,
+    total_months: u32,
+    system: AmortizationSystem,
+) -> Vec<Decimal> {
+    if total_months == 0 {
+        return Vec::new();
+    }
+
+    let fixed_payment = price_fixed_payment(total_amount, monthly_rate, total_months);
+    let fixed_amortization = total_amount / Decimal::from(total_months);
+    let mut current_balance = total_amount;
+
+    (0..total_months)
+        .map(|month| {
+            let amortization = match system {
+                AmortizationSystem::Price => fixed_payment - current_balance * monthly_rate,
+                AmortizationSystem::Sac => {
+                    current_balance = current_balance.max(dec!(0));
+                    sac_month_amortization(current_balance, fixed_amortization, month == total_months - 1)
+                }
+            };
+            current_balance -= amortization;
+            current_balance.max(dec!(0))
+        })
+        .collect()
+}
+
+/// The results of a reverse mortgage schedule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct ReverseMortgageResult {
+    /// The balance owed at the end of the term.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub final_balance: Decimal,
+    /// The total received by the borrower, including the initial draw.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub total_drawn: Decimal,
+    /// A vector containing the payment details for each month.
+    pub amortization_curve: Vec<MonthPayment>,
+}
+
+/// Calculates the schedule of a reverse mortgage, where the balance grows over time.
+///
+/// The borrower receives `initial_draw` at signing and `monthly_draw` at the end of each
+/// month. Every month the balance grows by its interest plus the draw, so the
+/// amortization is negative, `-(interest + draw)`, and each month's `installment()` is
+/// `-monthly_draw`, the amount received.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero.
+pub fn calculate_reverse_mortgage(
+    initial_draw: Decimal,
+    monthly_draw: Decimal,
+    monthly_rate: Decimal,
+    total_months: u32,
+) -> Result<ReverseMortgageResult, FinancingError> {
+    if total_months == 0 {
+        return Err(FinancingError::ZeroMonths);
+    }
+
+    let mut current_balance = initial_draw;
+    let mut amortization_curve = Vec::with_capacity(total_months as usize);
+
+    for _ in 0..total_months {
+        let interest_payment = current_balance * monthly_rate;
+        let amortization = -(interest_payment + monthly_draw);
+        current_balance -= amortization;
+        amortization_curve.push(
+            MonthPayment {
+                new_balance: current_balance,
+                current_amortization: amortization,
+                current_interest: interest_payment,
+                accrued_interest: dec!(0),
+                admin_fee: dec!(0),
+                insurance: dec!(0),
+                principal_paid_to_date: initial_draw - current_balance,
+                present_value: None,
+                applied_rate: None,
+                employer_subsidy: dec!(0),
+            }
+        );
+        #[cfg(feature = "tracing")]
+        trace_month(0, &amortization_curve);
+    }
+
+    Ok(ReverseMortgageResult {
+        final_balance: current_balance.round_dp(2),
+        total_drawn: (initial_draw + monthly_draw * Decimal::from(total_months)).round_dp(2),
+        amortization_curve,
+    })
+}
+
+/// Calculates the total paid in today's money, deflating by cumulative inflation.
+///
+/// Installment `k` (1-based) is divided by `(1 + monthly_inflation_rate)^k`, which is
+/// `npv` of the installments at the inflation rate. The result is rounded to cents.
+pub fn total_paid_real(amortization_curve: &[MonthPayment], monthly_inflation_rate: Decimal) -> Decimal {
+    let installments: Vec<Decimal> = amortization_curve.iter().map(MonthPayment::installment).collect();
+
+    npv(&installments, monthly_inflation_rate).round_dp(2)
+}
+
+/// Returns the rate effectively charged in each month, `current_interest / opening_balance`,
+/// as an audit trail for indexed or stepped loans.
+///
+/// A plain fixed-rate schedule yields the contract rate in every entry. Months with a
+/// zero opening balance yield zero instead of dividing by it (see
+/// `MonthPayment::realized_rate`).
+pub fn effective_rate_curve(amortization_curve: &[MonthPayment]) -> Vec<Decimal> {
+    amortization_curve.iter().map(MonthPayment::realized_rate).collect()
+}
+
+/// The results of a schedule mixing SAC and Price sub-loans.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct MixedTableResult {
+    /// The first combined installment.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub first_payment: Decimal,
+    /// The last combined installment.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub last_payment: Decimal,
+    /// The total amount paid over the lifetime of the loan.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub total_paid: Decimal,
+    /// The month-by-month sum of both sub-loans' schedules.
+    pub amortization_curve: Vec<MonthPayment>,
+}
+
+/// Calculates a hybrid schedule amortizing part of the principal by SAC and the rest by
+/// Price, at the same rate and term.
+///
+/// The principal is split into `total_amount * sac_fraction` for SAC and the remainder
+/// for Price, each sub-loan is calculated on its own, and every field of their months is
+/// summed into one combined curve.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero or `sac_fraction` is outside `[0, 1]`.
+pub fn calculate_mixed(
+    total_amount: Decimal,
+    sac_fraction: Decimal,
+    monthly_rate: Decimal,
+    total_months: u32,
+) -> Result<MixedTableResult, FinancingError> {
+    if sac_fraction < dec!(0) || sac_fraction > dec!(1) {
+        return Err(FinancingError::InvalidArgument("SAC fraction must be between 0 and 1.".to_string()));
+    }
+
+    let sac_amount = total_amount * sac_fraction;
+    let sac = build_sac_table(sac_amount, monthly_rate.into(), total_months)?;
+    let price = build_price_table(total_amount - sac_amount, monthly_rate.into(), total_months)?;
+
+    let amortization_curve: Vec<MonthPayment> = sac
+        .amortization_curve
+        .iter()
+        .zip(&price.amortization_curve)
+        .map(|(sac, price)| MonthPayment {
+            new_balance: sac.new_balance + price.new_balance,
+            current_amortization: sac.current_amortization + price.current_amortization,
+            current_interest: sac.current_interest + price.current_interest,
+            accrued_interest: sac.accrued_interest + price.accrued_interest,
+            admin_fee: sac.admin_fee + price.admin_fee,
+            insurance: sac.insurance + price.insurance,
+            principal_paid_to_date: sac.principal_paid_to_date + price.principal_paid_to_date,
+            present_value: None,
+            applied_rate: None,
+            employer_subsidy: dec!(0),
+        })
+        .collect();
+
+    let installment_at =
+        |payment: Option<&MonthPayment>| payment.map(MonthPayment::installment).unwrap_or_default().round_dp(2);
+    Ok(MixedTableResult {
+        first_payment: installment_at(amortization_curve.first()),
+        last_payment: installment_at(amortization_curve.last()),
+        total_paid: amortization_curve.iter().map(MonthPayment::installment).sum::<Decimal>().round_dp(2),
+        amortization_curve,
+    })
+}
+
+/// Calculates the monthly deposit needed to save a down payment (entrada) in `months`.
+///
+/// Deposits are made at the end of each month and earn `savings_monthly_rate`, so this
+/// is the inverse of the future value of an annuity: `PMT = FV * i / [(1 + i)^n - 1]`,
+/// or `FV / n` at a zero rate. For positive rates it is evaluated with the discount
+/// factor `v = 1 / (1 + i)` as `FV * i * v^n / (1 - v^n)`, which cannot overflow for
+/// long terms. The result is rounded to cents.
+///
+/// # Errors
+///
+/// Returns an error if `months` is zero or the rate is at or below -100%.
+pub fn savings_plan_for_down_payment(
+    target_down_payment: Decimal,
+    months: u32,
+    savings_monthly_rate: Decimal,
+) -> Result<Decimal, FinancingError> {
+    if months == 0 {
+        return Err(FinancingError::ZeroMonths);
+    }
+    if savings_monthly_rate <= dec!(-1) {
+        return Err(FinancingError::RateOutOfRange(savings_monthly_rate * dec!(100)));
+    }
+    if savings_monthly_rate.is_zero() {
+        return Ok((target_down_payment / Decimal::from(months)).round_dp(2));
+    }
+
+    let deposit = if savings_monthly_rate > dec!(0) {
+        let discount = (dec!(1) / (dec!(1) + savings_monthly_rate)).powu(months.into());
+        target_down_payment * savings_monthly_rate * discount / (dec!(1) - discount)
+    } else {
+        let growth = (dec!(1) + savings_monthly_rate).powu(months.into());
+        target_down_payment * savings_monthly_rate / (growth - dec!(1))
+    };
+    Ok(deposit.round_dp(2))
+}
+
+/// Sums the last `n` installments of a curve, with `n` clamped to its length.
+pub fn tail_total(amortization_curve: &[MonthPayment], n: usize) -> Decimal {
+    let start = amortization_curve.len().saturating_sub(n);
+
+    amortization_curve[start..].iter().map(MonthPayment::installment).sum()
+}
+
+/// Returns whether a curve pays down principal at least as fast as a benchmark in every
+/// month.
+///
+/// Each month's cumulative amortization fraction, `principal_paid_to_date` over the
+/// principal, is compared with the benchmark fraction for the same month (0.1 means 10%
+/// of the principal repaid). When the lengths differ only the shorter range is compared.
+/// Fractions are compared at 8 decimal places so rounding residue in the final month
+/// does not decide the outcome. Returns false when there is nothing to compare or the
+/// principal is zero.
+///
+/// # Arguments
+///
+/// * `amortization_curve` - The schedule under evaluation.
+/// * `benchmark` - The reference cumulative amortization fraction for each month.
+pub fn amortizes_faster_than(amortization_curve: &[MonthPayment], benchmark: &[Decimal]) -> bool {
+    let principal = match amortization_curve.first() {
+        Some(first) => first.opening_balance(),
+        None => return false,
+    };
+    if principal.is_zero() || benchmark.is_empty() {
+        return false;
+    }
+
+    amortization_curve
+        .iter()
+        .zip(benchmark)
+        .all(|(month, &reference)| (month.principal_paid_to_date / principal).round_dp(8) >= reference.round_dp(8))
+}
+
+/// Calculates the total paid and the payoff month when the same lump sum is prepaid
+/// every year.
+///
+/// The lump sum is applied with `apply_prepayment` after installments 12, 24, 36 and so
+/// on, for as long as installments remain. The total paid is the sum of the installments
+/// plus the lump sums actually applied, a prepayment above the outstanding balance
+/// counting only for that balance. The payoff month is the last installment actually
+/// paid (1-based), which is `total_months` unless the prepayments end the loan earlier.
+///
+/// # Arguments
+///
+/// * `input` - The loan parameters.
+/// * `annual_amount` - The lump sum prepaid every 12 installments.
+/// * `strategy` - How each prepayment is absorbed.
+/// * `system` - The amortization system of the schedule.
+///
+/// # Errors
+///
+/// Returns an error if the input is invalid or the lump sum is negative.
+pub fn total_paid_with_annual_prepayment(
+    input: DebtCalculationInput,
+    annual_amount: Decimal,
+    strategy: PrepaymentStrategy,
+    system: AmortizationSystem,
+) -> Result<(Decimal, u32), FinancingError> {
+    if annual_amount < dec!(0) {
+        return Err(FinancingError::InvalidArgument("Prepayment cannot be negative.".to_string()));
+    }
+
+    let result = calculate(&LoanConfig::from(input))?;
+    let mut curve = match system {
+        AmortizationSystem::Price => result.price_table.amortization_curve,
+        AmortizationSystem::Sac => result.sac_table.amortization_curve,
+    };
+    let mut prepaid = dec!(0);
+    let mut month = 12;
+    while month < curve.len() {
+        prepaid += annual_amount.min(curve[month - 1].new_balance);
+        curve = apply_prepayment(&curve, month as u32, annual_amount, strategy, system)?;
+        month += 12;
+    }
+    let installments = curve.iter().map(MonthPayment::installment).sum::<Decimal>();
+
+    Ok(((installments + prepaid).round_dp(2), curve.len() as u32))
+}
+
+/// Returns the interest charged in a single month without building the schedule.
+///
+/// The opening balance of `month` (1-based) is obtained directly: `P - (m - 1) * A` for
+/// SAC and the closed form `B_k = P(1 + i)^k - PMT[(1 + i)^k - 1] / i` for Price, as in
+/// `sac_tail` and `price_tail`. The result matches
+/// `amortization_curve[month - 1].current_interest` up to the last digits of `Decimal`
+/// precision. Returns zero when `month` is outside `1..=total_months`.
+///
+/// # Arguments
+///
+/// * `total_amount` - The principal loan amount.
+/// * `monthly_rate` - The effective monthly interest rate as a decimal (not percentage).
+/// * `total_months` - The total number of payments.
+/// * `system` - The amortization system of the schedule.
+/// * `month` - The installment to look up (1-based).
+pub fn interest_at_month(
+    total_amount: Decimal,
+    monthly_rate: Decimal,
+    total_months: u32,
+    system: AmortizationSystem,
+    month: u32,
+) -> Decimal {
+    if month == 0 || month > total_months || monthly_rate.is_zero() {
+        return dec!(0);
+    }
+
+    let elapsed = month - 1;
+    let opening_balance = match system {
+        AmortizationSystem::Price => {
+            let fixed_payment = price_fixed_payment(total_amount, monthly_rate, total_months);
+            let growth = (dec!(1) + monthly_rate).powu(elapsed.into());
+            total_amount * growth - fixed_payment * (growth - dec!(1)) / monthly_rate
+        }
+        AmortizationSystem::Sac => {
+            total_amount - Decimal::from(elapsed) * (total_amount / Decimal::from(total_months))
+        }
+    };
+
+    opening_balance.max(dec!(0)) * monthly_rate
+}
+
+/// Returns whether the SAC and Price results are practically the same, so comparing
+/// them is pointless (very short terms or tiny rates).
+///
+/// Both the total paid and every month's installment must differ by less than
+/// `tolerance`; schedules of different lengths are never equivalent.
+pub fn systems_are_equivalent(sac: &SacTableResult, price: &PriceTableResult, tolerance: Decimal) -> bool {
+    sac.amortization_curve.len() == price.amortization_curve.len()
+        && (sac.total_paid - price.total_paid).abs() < tolerance
+        && sac
+            .amortization_curve
+            .iter()
+            .zip(&price.amortization_curve)
+            .all(|(sac, price)| (sac.installment() - price.installment()).abs() < tolerance)
+}
+
+/// Calculates the interest attributable to compounding ("juros sobre juros").
+///
+/// Each installment (amortization plus interest) is split into the principal it repays,
+/// its value discounted at compound interest, `installment / (1 + i)^k`, and the interest
+/// on that principal. The same principal charged simple interest for `k` months would
+/// cost `principal * i * k`. The result is the total interest of the curve minus the
+/// simple interest of all installments, rounded to cents. It is zero for a zero rate or
+/// a one-month loan, and positive otherwise.
+///
+/// # Arguments
+///
+/// * `amortization_curve` - The schedule to analyze.
+/// * `monthly_rate` - The monthly contract rate as a decimal (not percentage).
+pub fn compound_interest_excess(amortization_curve: &[MonthPayment], monthly_rate: Decimal) -> Decimal {
+    let mut discount_factor = dec!(1);
+    let mut simple_interest = dec!(0);
+    for (index, payment) in amortization_curve.iter().enumerate() {
+        discount_factor /= dec!(1) + monthly_rate;
+        let principal = (payment.current_amortization + payment.current_interest) * discount_factor;
+        simple_interest += principal * monthly_rate * Decimal::from(index + 1);
+    }
+
+    (total_interest(amortization_curve) - simple_interest).round_dp(2)
+}
+
+/// Calculates a SAC schedule with a term known at compile time into a stack array.
+///
+/// The rows match `calculate_sac_table(total_amount, monthly_interest_rate, N)` exactly,
+/// without allocating. A zero `N` fails to compile.
+///
+/// # Arguments
+///
+/// * `total_amount` - The principal loan amount.
+/// * `monthly_interest_rate` - The effective monthly interest rate, see `MonthlyRate`.
+pub fn sac_schedule_array<const N: usize>(
+    total_amount: Decimal,
+    monthly_interest_rate: MonthlyRate,
+) -> [MonthPayment; N] {
+    const { assert!(N > 0, "the schedule needs at least one month") };
+
+    let monthly_interest_rate = monthly_interest_rate.value();
+    let fixed_amortization = total_amount / Decimal::from(N);
+    let mut current_balance = total_amount;
+
+    std::array::from_fn(|month| {
+        current_balance = current_balance.max(dec!(0));
+        let interest_payment = current_balance * monthly_interest_rate;
+        let amortization = sac_month_amortization(current_balance, fixed_amortization, month == N - 1);
+        current_balance -= amortization;
+        MonthPayment {
+            principal_paid_to_date: total_amount - current_balance,
+            ..MonthPayment::new(current_balance.max(dec!(0)), amortization, interest_payment)
+        }
+    })
+}
+
+/// Calculates a Price schedule with a term known at compile time into a stack array.
+///
+/// The rows match `calculate_price_table(total_amount, monthly_interest_rate, N)`
+/// exactly, without allocating. A zero `N` fails to compile.
+///
+/// # Arguments
+///
+/// * `total_amount` - The principal loan amount.
+/// * `monthly_interest_rate` - The effective monthly interest rate, see `MonthlyRate`.
+pub fn price_schedule_array<const N: usize>(
+    total_amount: Decimal,
+    monthly_interest_rate: MonthlyRate,
+) -> [MonthPayment; N] {
+    const { assert!(N > 0, "the schedule needs at least one month") };
+
+    let monthly_interest_rate = monthly_interest_rate.value();
+    let fixed_payment = price_fixed_payment(total_amount, monthly_interest_rate, N as u32);
+    let mut current_balance = total_amount;
+
+    std::array::from_fn(|_| {
+        let interest_payment = current_balance * monthly_interest_rate;
+        let amortization = fixed_payment - interest_payment;
+        current_balance -= amortization;
+        MonthPayment {
+            principal_paid_to_date: total_amount - current_balance,
+            ..MonthPayment::new(current_balance.max(dec!(0)), amortization, interest_payment)
+        }
+    })
+}
+
+/// How installments are rounded to cents by `rounded_installments`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoundingPolicy {
+    /// Each installment is rounded to the cent on its own, so the rounded sum may drift
+    /// from the unrounded total.
+    #[default]
+    PerMonth,
+    /// The rounding error of each installment is carried forward into the next one, so a
+    /// later installment takes an extra cent once the fractions add up to half a cent.
+    /// The rounded sum equals the unrounded total to the cent.
+    CarryForward,
+}
+
+/// Returns the installments of a curve rounded to cents according to `policy`.
+pub fn rounded_installments(amortization_curve: &[MonthPayment], policy: RoundingPolicy) -> Vec<Decimal> {
+    let mut carry = dec!(0);
+    amortization_curve
+        .iter()
+        .map(|payment| match policy {
+            RoundingPolicy::PerMonth => payment.installment().round_dp(2),
+            RoundingPolicy::CarryForward => {
+                let unrounded = payment.installment() + carry;
+                let rounded = unrounded.round_dp(2);
+                carry = unrounded - rounded;
+                rounded
+            }
+        })
+        .collect()
+}
+
+/// Returns, for each month of a SAC schedule, the share of the installment that is
+/// interest, `current_interest / installment`.
+///
+/// Because SAC interest tracks the declining balance while the amortization is fixed,
+/// the share falls month after month. Months with a zero installment yield zero.
+pub fn interest_share_curve(sac: &SacTableResult) -> Vec<Decimal> {
+    sac.amortization_curve
+        .iter()
+        .map(|payment| {
+            let installment = payment.installment();
+            if installment.is_zero() {
+                dec!(0)
+            } else {
+                payment.current_interest / installment
+            }
+        })
+        .collect()
+}
+
+/// The results of applying a one-month rate promotion to a schedule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct RatePromoResult {
+    /// The interest saved in the promotional month, rounded to cents.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub interest_saved: Decimal,
+    /// The schedule after the promotion.
+    pub amortization_curve: Vec<MonthPayment>,
+}
+
+/// Charges a reduced rate for a single month of a schedule.
+///
+/// The interest of `at_month` (1-based) is recomputed at `promo_monthly_rate` while its
+/// installment is kept, so the interest saved is amortized instead. The later months
+/// follow from the lower balance as with a `PrepaymentStrategy::ReduceTerm` prepayment:
+/// the Price installment or SAC amortization is kept and the loan ends earlier. In the
+/// last month the installment itself is reduced.
+///
+/// # Errors
+///
+/// Returns an error if `at_month` is outside the curve, or if the promotional rate is
+/// negative or above the rate charged that month.
+pub fn apply_rate_promo(
+    amortization_curve: &[MonthPayment],
+    at_month: u32,
+    promo_monthly_rate: Decimal,
+    system: AmortizationSystem,
+) -> Result<RatePromoResult, FinancingError> {
+    let Some(original) = (at_month as usize).checked_sub(1).and_then(|index| amortization_curve.get(index)) else {
+        return Err(FinancingError::InvalidArgument("Promotion month is outside the schedule.".to_string()));
+    };
+    if promo_monthly_rate < dec!(0) || promo_monthly_rate > original.realized_rate() {
+        return Err(FinancingError::InvalidArgument(
+            "Promotional rate must be between zero and the rate charged that month.".to_string(),
+        ));
+    }
+
+    let saving = original.current_interest - original.opening_balance() * promo_monthly_rate;
+    let is_last_month = at_month as usize == amortization_curve.len();
+    let mut amortization_curve = if is_last_month {
+        amortization_curve.to_vec()
+    } else {
+        apply_prepayment(amortization_curve, at_month, saving, PrepaymentStrategy::ReduceTerm, system)?
+    };
+
+    // In the last month there is no balance left to amortize, so the installment shrinks.
+    let promo_month = &mut amortization_curve[at_month as usize - 1];
+    promo_month.current_interest -= saving;
+    if !is_last_month {
+        promo_month.current_amortization += saving;
+    }
+
+    Ok(RatePromoResult { interest_saved: saving.round_dp(2), amortization_curve })
+}
+
+/// Sums the outstanding principal of a portfolio of loans at a calendar month.
+///
+/// Each loan is a `(start_month, result)` pair: the loan is signed in calendar month
+/// `start_month`, and its installment `k` falls in month `start_month + k`. At
+/// `calendar_month` a loan contributes its balance after the installments paid so far,
+/// the full principal in its start month, and zero before it starts or once paid off.
+///
+/// # Arguments
+///
+/// * `loans` - The loans with their start month.
+/// * `calendar_month` - The month at which the exposure is measured.
+/// * `system` - Which of each result's schedules to read.
+pub fn portfolio_outstanding_at(
+    loans: &[(u32, DebtTrajectoryResult)],
+    calendar_month: u32,
+    system: AmortizationSystem,
+) -> Decimal {
+    loans
+        .iter()
+        .filter_map(|(start_month, result)| {
+            let elapsed = calendar_month.checked_sub(*start_month)? as usize;
+            let curve = match system {
+                AmortizationSystem::Price => &result.price_table.amortization_curve,
+                AmortizationSystem::Sac => &result.sac_table.amortization_curve,
+            };
+            match elapsed {
+                0 => curve.first().map(MonthPayment::opening_balance),
+                _ => curve.get(elapsed - 1).map(|payment| payment.new_balance),
+            }
+        })
+        .sum()
+}
+
+/// The results of applying a plan of prepayments to a schedule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct PrepaymentScheduleResult {
+    /// The month (1-based) of the last installment after all prepayments.
+    pub payoff_month: u32,
+    /// The total interest saved by the prepayments, compared to the original schedule.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub interest_saved: Decimal,
+    /// The schedule after all prepayments.
+    pub amortization_curve: Vec<MonthPayment>,
+}
+
+/// Applies a plan of `(month, amount)` lump-sum prepayments to a schedule.
+///
+/// The prepayments are applied in month order with `apply_prepayment`, each on the
+/// schedule left by the previous ones.
+///
+/// # Errors
+///
+/// Returns an error if a prepayment month is zero or an amount is negative, and an error
+/// listing every prepayment that falls at or after the last remaining installment,
+/// including those made unreachable by earlier prepayments shortening the term.
+pub fn apply_prepayment_schedule(
+    amortization_curve: &[MonthPayment],
+    prepayments: &[(u32, Decimal)],
+    strategy: PrepaymentStrategy,
+    system: AmortizationSystem,
+) -> Result<PrepaymentScheduleResult, FinancingError> {
+    let mut prepayments = prepayments.to_vec();
+    prepayments.sort_by_key(|(month, _)| *month);
+
+    let mut interest_saved = dec!(0);
+    let mut curve = amortization_curve.to_vec();
+    let mut skipped = Vec::new();
+    for (month, amount) in prepayments {
+        if month == 0 || (month as usize) < curve.len() {
+            let after = apply_prepayment(&curve, month, amount, strategy, system)?;
+            interest_saved += (total_interest(&curve) - total_interest(&after)).round_dp(2);
+            curve = after;
+        } else {
+            skipped.push(format!("{amount} in month {month}"));
+        }
+    }
+    if !skipped.is_empty() {
+        return Err(FinancingError::InvalidArgument(format!(
+            "Prepayments after the last installment (month {}) cannot be applied: {}.",
+            curve.len(),
+            skipped.join(", ")
+        )));
+    }
+
+    Ok(PrepaymentScheduleResult { payoff_month: curve.len() as u32, interest_saved, amortization_curve: curve })
+}
+
+/// Returns the first month (1-based) of a Price schedule whose amortization exceeds its
+/// interest, the point where the installment starts paying mostly principal.
+///
+/// Returns `None` when no month crosses over, e.g. for extreme rates.
+pub fn price_interest_principal_crossover(table: &PriceTableResult) -> Option<u32> {
+    table
+        .amortization_curve
+        .iter()
+        .position(|payment| payment.current_amortization > payment.current_interest)
+        .map(|index| index as u32 + 1)
+}
+
+/// How a due date that is not a business day is moved.
+#[cfg(feature = "chrono")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BusinessDayConvention {
+    /// The installment is due on the next business day.
+    #[default]
+    Following,
+    /// The installment is due on the previous business day.
+    Preceding,
+}
+
+/// The non-business days used to adjust installment due dates: weekends plus the
+/// holidays listed.
+#[cfg(feature = "chrono")]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BusinessDayCalendar {
+    /// The holidays on which no installment can be due.
+    pub holidays: HashSet<NaiveDate>,
+    /// How a due date on a weekend or holiday is moved.
+    pub convention: BusinessDayConvention,
+}
+
+#[cfg(feature = "chrono")]
+impl BusinessDayCalendar {
+    /// Returns whether `date` is neither a weekend nor a holiday.
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains(&date)
+    }
+
+    /// Moves `date` to a business day according to the convention, leaving business days
+    /// unchanged.
+    pub fn adjust(&self, date: NaiveDate) -> NaiveDate {
+        let mut adjusted = date;
+        while !self.is_business_day(adjusted) {
+            adjusted = match self.convention {
+                BusinessDayConvention::Following => adjusted + Days::new(1),
+                BusinessDayConvention::Preceding => adjusted - Days::new(1),
+            };
+        }
+
+        adjusted
+    }
+}
+
+/// An installment with its due date.
+#[cfg(feature = "chrono")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct ScheduledPayment {
+    /// The date the installment is due, moved to a business day.
+    pub due_date: NaiveDate,
+    /// The payment details of the installment.
+    pub payment: MonthPayment,
+}
+
+/// Calculates a schedule whose installments fall due on business days.
+///
+/// Installment `k` (1-based) is nominally due on `first_due_date` plus `k - 1` months
+/// and is moved off weekends and holidays by `calendar`. With `DayCount::Monthly30` only
+/// the dates change. With `DayCount::Actual365` each period accrues interest for the
+/// actual days between consecutive adjusted due dates, the first period starting one
+/// month before `first_due_date`, and the Price installment is solved on those periods
+/// so the loan is repaid without a residual in the last installment.
+///
+/// # Arguments
+///
+/// * `total_amount` - The principal loan amount.
+/// * `interest_per_year` - The annual interest rate as a percentage (e.g., 10.5 for 10.5%).
+/// * `total_months` - The total number of payments.
+/// * `system` - The amortization system of the schedule.
+/// * `day_count` - How interest accrues over each period.
+/// * `first_due_date` - The nominal due date of the first installment.
+/// * `calendar` - The holidays and business-day convention.
+///
+/// # Errors
+///
+/// Returns an error if `total_months` is zero, the rate is out of range, or a due date
+/// falls outside the range `NaiveDate` supports.
+#[cfg(feature = "chrono")]
+pub fn business_day_schedule(
+    total_amount: Decimal,
+    interest_per_year: Decimal,
+    total_months: u32,
+    system: AmortizationSystem,
+    day_count: DayCount,
+    first_due_date: NaiveDate,
+    calendar: &BusinessDayCalendar,
+) -> Result<Vec<ScheduledPayment>, FinancingError> {
+    let rate = MonthlyRate::from_annual_percent(interest_per_year)?;
+    let amortization_curve = match system {
+        AmortizationSystem::Price => build_price_table(total_amount, rate, total_months)?.amortization_curve,
+        AmortizationSystem::Sac => build_sac_table(total_amount, rate, total_months)?.amortization_curve,
+    };
+
+    let out_of_range =
+        || FinancingError::InvalidArgument("Due dates are outside the supported date range.".to_string());
+    let due_dates = (0..total_months)
+        .map(|month| first_due_date.checked_add_months(Months::new(month)).map(|date| calendar.adjust(date)))
+        .collect::<Option<Vec<NaiveDate>>>()
+        .ok_or_else(out_of_range)?;
+    let amortization_curve = match day_count {
+        DayCount::Monthly30 => amortization_curve,
+        DayCount::Actual365 => {
+            let mut period_start = first_due_date.checked_sub_months(Months::new(1)).ok_or_else(out_of_range)?;
+            let period_days: Vec<u32> = due_dates
+                .iter()
+                .map(|due_date| {
+                    let days = (*due_date - period_start).num_days().max(0) as u32;
+                    period_start = *due_date;
+                    days
+                })
+                .collect();
+            actual_day_count_curve(total_amount, interest_per_year / dec!(100), &period_days, system).1
+        }
+    };
+
+    Ok(due_dates
+        .into_iter()
+        .zip(amortization_curve)
+        .map(|(due_date, payment)| ScheduledPayment { due_date, payment })
+        .collect())
+}
+
+/// The comparison between keeping a loan and refinancing it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "camel_case", serde(rename_all = "camelCase"))]
+pub struct RefinanceResult {
+    /// The sum of the remaining installments of the current loan, rounded to cents.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub stay_total_cost: Decimal,
+    /// The sum of the installments of the new loan plus the refinance cost, rounded to
+    /// cents.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub refinance_total_cost: Decimal,
+    /// `stay_total_cost - refinance_total_cost`; negative when refinancing costs more.
+    #[serde(serialize_with = "json_decimal::serialize")]
+    pub savings: Decimal,
+    /// The first month (1-based) in which the cumulative installment savings cover the
+    /// refinance cost, or `None` if they never do or refinancing does not save anything
+    /// in total.
+    pub break_even_month: Option<u32>,
+    /// The schedule of the new loan.
+    pub amortization_curve: Vec<MonthPayment>,
+}
+
+/// Compares keeping the current loan with refinancing its balance.
+///
+/// The current loan is the schedule of `current_balance` over `remaining_months` at
+/// `current_rate`; the new loan refinances the same balance over `new_months` at
+/// `new_rate`, paying `refi_cost` upfront. The break-even month accumulates the
+/// month-by-month differences between the two installments, as in `points_break_even`,
+/// counting a loan that has already ended as paying nothing. It is only reported when
+/// `savings` is positive: a longer term can cover the cost early through lower
+/// installments and still cost more over its life.
+///
+/// # Arguments
+///
+/// * `current_balance` - The outstanding balance of the current loan.
+/// * `current_rate` - The monthly rate of the current loan, as a decimal.
+/// * `remaining_months` - The installments left on the current loan.
+/// * `new_rate` - The monthly rate of the new loan, as a decimal.
+/// * `new_months` - The term of the new loan.
+/// * `refi_cost` - The upfront cost of refinancing.
+/// * `system` - The amortization system of both schedules.
+///
+/// # Errors
+///
+/// Returns an error if `remaining_months` or `new_months` is zero.
+pub fn refinance_analysis(
+    current_balance: Decimal,
+    current_rate: Decimal,
+    remaining_months: u32,
+    new_rate: Decimal,
+    new_months: u32,
+    refi_cost: Decimal,
+    system: AmortizationSystem,
+) -> Result<RefinanceResult, FinancingError> {
+    let curve = |rate: Decimal, months: u32| match system {
+        AmortizationSystem::Price => build_price_table(current_balance, rate.into(), months).map(|t| t.amortization_curve),
+        AmortizationSystem::Sac => build_sac_table(current_balance, rate.into(), months).map(|t| t.amortization_curve),
+    };
+    let stay = curve(current_rate, remaining_months)?;
+    let refinance = curve(new_rate, new_months)?;
+
+    let stay_total_cost = stay.iter().map(MonthPayment::installment).sum::<Decimal>().round_dp(2);
+    let refinance_total_cost = (refinance.iter().map(MonthPayment::installment).sum::<Decimal>() + refi_cost).round_dp(2);
+    let savings = stay_total_cost - refinance_total_cost;
+
+    let installment_at = |curve: &[MonthPayment], month: usize| curve.get(month).map(MonthPayment::installment).unwrap_or_default();
+    let mut saving = dec!(0);
+    let mut break_even_month = None;
+    let months = if savings > dec!(0) { stay.len().max(refinance.len()) } else { 0 };
+    for month in 0..months {
+        saving += installment_at(&stay, month) - installment_at(&refinance, month);
+        if saving >= refi_cost {
+            break_even_month = Some(month as u32 + 1);
+            break;
+        }
+    }
+
+    Ok(RefinanceResult {
+        stay_total_cost,
+        refinance_total_cost,
+        savings,
+        break_even_month,
+        amortization_curve: refinance,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_calculate_debt_trajectory_happy_path() {
+        let input = DebtCalculationInput {
+            total_amount: dec!(12000),
+            interest_per_year: dec!(12),
+            down_payment_percent: dec!(0),
+            total_months: 12,
+            signing_to_first_payment_months: 0,
+        };
+
+        let result = calculate_debt_trajectory(input).unwrap();
+
+        // Assertions for SAC table
+        assert_eq!(result.sac_table.fixed_amortization.round_dp(2), dec!(1000.00));
+        assert_eq!(result.sac_table.first_payment.round_dp(2), dec!(1113.87));
+        assert_eq!(result.sac_table.last_payment.round_dp(2), dec!(1009.49));
+        assert_eq!(result.sac_table.total_paid.round_dp(2), dec!(12740.13));
+
+        // Assertions for Price table
+        assert_eq!(result.price_table.fixed_payment.round_dp(2), dec!(1062.74));
+        assert_eq!(result.price_table.total_paid.round_dp(2), dec!(12752.94));
+    }
+
+    #[test]
+    fn test_calculate_debt_trajectory_with_down_payment10() {
+        let input = DebtCalculationInput {
+            total_amount: dec!(12000),
+            interest_per_year: dec!(12),
+            down_payment_percent: dec!(10.0),
+            total_months: 12,
+            signing_to_first_payment_months: 0,
+        };
+
+        let result = calculate_debt_trajectory(input).unwrap();
+
+        assert_eq!(result.financed_amount.round_dp(2), dec!(10800.00));
+
+        // Assertions for SAC table
+        assert_eq!(result.sac_table.fixed_amortization.round_dp(2), dec!(900.00));
+        assert_eq!(result.sac_table.first_payment.round_dp(2), dec!(1002.48));
+        assert_eq!(result.sac_table.last_payment.round_dp(2), dec!(908.54));
+        assert_eq!(result.sac_table.total_paid.round_dp(2), dec!(11466.11));
+
+        // Assertions for Price table
+        assert_eq!(result.price_table.fixed_payment.round_dp(2), dec!(956.47));
+        assert_eq!(result.price_table.total_paid.round_dp(2), dec!(11477.64));
+    }
+
+    #[test]
+    fn test_calculate_debt_trajectory_with_down_payment40() {
+        let input = DebtCalculationInput {
+            total_amount: dec!(12000),
+            interest_per_year: dec!(12),
+            down_payment_percent: dec!(40),
+            total_months: 12,
+            signing_to_first_payment_months: 0,
+        };
+
+        let result = calculate_debt_trajectory(input).unwrap();
+
+        assert_eq!(result.financed_amount.round_dp(2), dec!(7200.00));
+
+        // Assertions for SAC table
+        assert_eq!(result.sac_table.fixed_amortization.round_dp(2), dec!(600.00));
+        assert_eq!(result.sac_table.first_payment.round_dp(2), dec!(668.32));
+        assert_eq!(result.sac_table.last_payment.round_dp(2), dec!(605.69));
+        assert_eq!(result.sac_table.total_paid.round_dp(2), dec!(7644.08));
+
+        // Assertions for Price table
+        assert_eq!(result.price_table.fixed_payment.round_dp(2), dec!(637.65));
+        assert_eq!(result.price_table.total_paid.round_dp(2), dec!(7651.76));
+    }
+
+    #[test]
+    fn test_normalize_annual_interest_rate() {
+        // 12% per year should be a bit less than 1% per month when compounded.
+        let annual_rate = dec!(12);
+        let monthly_rate = normalize_annual_interest_rate(annual_rate);
+        // Effective monthly rate for 12% annual is approx 0.9488%
+        // (1.12)^(1/12) - 1 = 0.009488...
+        // Let's check for a value in that range.
+        assert!(monthly_rate > dec!(0.0094) && monthly_rate < dec!(0.0095));
+    }
+
+    #[test]
+    fn test_zero_months_error() {
+        let input = DebtCalculationInput {
+            total_amount: dec!(100000),
+            interest_per_year: dec!(10),
+            down_payment_percent: dec!(0),
+            total_months: 0,
+            signing_to_first_payment_months: 0,
+        };
+        let result = calculate_debt_trajectory(input);
+        assert!(result.is_err());
+    }
+
+    fn assert_same_rows(tail: &[MonthPayment], full: &[MonthPayment]) {
+        assert_eq!(tail.len(), full.len());
+        for (a, b) in tail.iter().zip(full) {
+            assert_eq!(a.new_balance.round_dp(8), b.new_balance.round_dp(8));
+            assert_eq!(a.current_amortization.round_dp(8), b.current_amortization.round_dp(8));
+            assert_eq!(a.current_interest.round_dp(8), b.current_interest.round_dp(8));
+        }
+    }
+
+    #[test]
+    fn test_sac_tail_matches_full_schedule() {
+        let rate = normalize_annual_interest_rate(dec!(10.5));
+        let full = calculate_sac_table(dec!(360000), rate.into(), 420).unwrap();
+        let tail = sac_tail(dec!(360000), rate, 420, 12).unwrap();
+
+        assert_same_rows(&tail, &full.amortization_curve[408..]);
+    }
+
+    #[test]
+    fn test_price_tail_matches_full_schedule() {
+        let rate = normalize_annual_interest_rate(dec!(10.5));
+        let full = calculate_price_table(dec!(360000), rate.into(), 420).unwrap();
+        let tail = price_tail(dec!(360000), rate, 420, 12).unwrap();
+
+        assert_same_rows(&tail, &full.amortization_curve[408..]);
+    }
+
+    #[test]
+    fn test_price_tail_with_zero_rate() {
+        let full = calculate_price_table(dec!(12000), dec!(0).into(), 12).unwrap();
+        let tail = price_tail(dec!(12000), dec!(0), 12, 3).unwrap();
+
+        assert_same_rows(&tail, &full.amortization_curve[9..]);
+        assert_eq!(tail[0].current_interest, dec!(0));
+        assert_eq!(tail[2].new_balance, dec!(0));
+    }
+
+    #[test]
+    fn test_tail_longer_than_term_returns_whole_schedule() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+        let full = calculate_sac_table(dec!(12000), rate.into(), 12).unwrap();
+        let tail = sac_tail(dec!(12000), rate, 12, 50).unwrap();
+
+        assert_same_rows(&tail, &full.amortization_curve);
+        assert!(price_tail(dec!(12000), rate, 0, 5).is_err());
+    }
+
+    #[test]
+    fn test_rate_context_matches_free_functions() {
+        let context = RateContext::new(dec!(12)).unwrap();
+        let rate = normalize_annual_interest_rate(dec!(12));
+        assert_eq!(context.monthly_interest_rate(), rate);
+
+        let cached = context.clone().with_terms_up_to(360);
+        for (amount, months) in [(dec!(12000), 12), (dec!(250000), 360), (dec!(1000), 420)] {
+            let price = calculate_price_table(amount, rate.into(), months).unwrap();
+            assert_eq!(context.price_table(amount, months).unwrap(), price);
+            assert_eq!(cached.price_table(amount, months).unwrap(), price);
+            let sac = context.sac_table(amount, months).unwrap();
+            assert_eq!(sac.total_paid, calculate_sac_table(amount, rate.into(), months).unwrap().total_paid);
+        }
+
+        assert!(context.price_table(dec!(1000), 0).is_err());
+    }
+
+    #[test]
+    fn test_signing_to_first_payment_accrued_interest() {
+        let base = DebtCalculationInput {
+            total_amount: dec!(12000),
+            interest_per_year: dec!(12),
+            down_payment_percent: dec!(0),
+            total_months: 12,
+            signing_to_first_payment_months: 0,
+        };
+        let delayed = DebtCalculationInput { signing_to_first_payment_months: 3, ..base.clone() };
+
+        let without = calculate_debt_trajectory(base).unwrap();
+        let result = calculate_debt_trajectory(delayed).unwrap();
+        let rate = normalize_annual_interest_rate(dec!(12));
+
+        assert_eq!(without.accrued_interest, dec!(0));
+        assert_eq!(result.accrued_interest, dec!(12000) * rate * dec!(3));
+        assert_eq!(result.accrued_interest.round_dp(2), dec!(341.60));
+
+        // Not capitalized: the amortization schedule itself is unchanged.
+        assert_eq!(result.price_table.fixed_payment, without.price_table.fixed_payment);
+        assert_eq!(
+            result.sac_table.amortization_curve[5].new_balance,
+            without.sac_table.amortization_curve[5].new_balance
+        );
+
+        // Spread across the first three installments only.
+        let curve = &result.price_table.amortization_curve;
+        assert!(curve[..3].iter().all(|p| p.accrued_interest > dec!(0)));
+        assert!(curve[3..].iter().all(|p| p.accrued_interest == dec!(0)));
+        let spread: Decimal = curve.iter().map(|p| p.accrued_interest).sum();
+        assert_eq!(spread.round_dp(10), result.accrued_interest.round_dp(10));
+        // Each total is rounded once from its curve, so their difference is 341.59 even
+        // though the 341.5965... accrual alone rounds to 341.60.
+        assert_eq!(result.price_table.total_paid - without.price_table.total_paid, dec!(341.59));
+        assert!(result.sac_table.first_payment > without.sac_table.first_payment);
+        assert_eq!(result.sac_table.last_payment, without.sac_table.last_payment);
+    }
+
+    #[test]
+    fn test_schedule_by_quarter() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+        let sac = calculate_sac_table(dec!(12000), rate.into(), 12).unwrap();
+        let start = YearMonth { year: 2025, month: 11 };
+
+        let quarters = schedule_by_quarter(&sac.amortization_curve, start).unwrap();
+
+        // Nov-Dec 2025, then Q1-Q3 2026, then Oct 2026.
+        assert_eq!(quarters.len(), 5);
+        assert_eq!((quarters[0].year, quarters[0].quarter), (2025, 4));
+        assert_eq!((quarters[1].year, quarters[1].quarter), (2026, 1));
+        assert_eq!((quarters[4].year, quarters[4].quarter), (2026, 4));
+        assert_eq!(quarters[0].total_amortization, dec!(2000));
+        assert_eq!(quarters[1].total_amortization, dec!(3000));
+        assert_eq!(quarters[1].ending_balance, sac.amortization_curve[4].new_balance);
+        assert_eq!(quarters[4].ending_balance, dec!(0));
+
+        let total: Decimal = quarters.iter().map(|q| q.total_installments).sum();
+        assert_eq!(total.round_dp(2), sac.total_paid);
+
+        assert!(schedule_by_quarter(&sac.amortization_curve, YearMonth { year: 2025, month: 13 }).is_err());
+    }
+
+    #[test]
+    fn test_single_month_loan() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+        let expected = dec!(100000) * (dec!(1) + rate);
+
+        let price = calculate_price_table(dec!(100000), rate.into(), 1).unwrap();
+        assert_eq!(price.fixed_payment, expected.round_dp(2));
+        assert_eq!(price.total_paid, expected.round_dp(2));
+        assert_eq!(price.amortization_curve.len(), 1);
+        assert_eq!(price.amortization_curve[0].current_amortization, dec!(100000));
+        assert_eq!(price.amortization_curve[0].new_balance, dec!(0));
+
+        let sac = calculate_sac_table(dec!(100000), rate.into(), 1).unwrap();
+        assert_eq!(sac.first_payment, expected.round_dp(2));
+        assert_eq!(sac.last_payment, expected.round_dp(2));
+        assert_eq!(sac.total_paid, expected.round_dp(2));
+        assert_eq!(sac.amortization_curve[0].new_balance, dec!(0));
+    }
+
+    #[test]
+    fn test_effective_annual_rate_round_trip() {
+        let rate = normalize_annual_interest_rate(dec!(10.5));
+        let price = calculate_price_table(dec!(360000), rate.into(), 420).unwrap();
+        let sac = calculate_sac_table(dec!(360000), rate.into(), 420).unwrap();
+
+        assert_eq!(effective_annual_rate(&price.amortization_curve).round_dp(6), dec!(0.105));
+        assert_eq!(effective_annual_rate(&sac.amortization_curve).round_dp(6), dec!(0.105));
+        assert_eq!(effective_annual_rate(&[]), dec!(0));
+    }
+
+    #[test]
+    fn test_normalize_rate_monthly_and_quarterly() {
+        assert_eq!(normalize_rate(dec!(12), 12).unwrap(), normalize_annual_interest_rate(dec!(12)));
+
+        // (1.12)^(1/4) - 1 = 0.028737...
+        let quarterly = normalize_rate(dec!(12), 4).unwrap();
+        assert_eq!(quarterly.round_dp(6), dec!(0.028737));
+        assert_eq!(((dec!(1) + quarterly).powu(4) - dec!(1)).round_dp(8), dec!(0.12));
+
+        assert_eq!(normalize_rate(dec!(12), 1).unwrap().round_dp(8), dec!(0.12));
+    }
+
+    #[test]
+    fn test_installment_split() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+        let sac = calculate_sac_table(dec!(12000), rate.into(), 12).unwrap();
+
+        let split = installment_split(&sac.amortization_curve);
+
+        assert_eq!(split.len(), 12);
+        assert!(split.iter().all(|(a, i)| a + i == dec!(1)));
+        assert_eq!(split[0].1.round_dp(4), (dec!(113.87) / dec!(1113.87)).round_dp(4));
+        assert!(split.windows(2).all(|w| w[1].1 < w[0].1));
+
+        let mut charged = sac.amortization_curve.clone();
+        apply_fee_schedule(&mut charged, &[dec!(25)]);
+        apply_mip(&mut charged, dec!(0.0003));
+        let split = installment_split(&charged);
+        let interest = sac.amortization_curve[0].current_interest;
+        assert_eq!(split[0].1, interest / (dec!(1000) + interest + dec!(25) + dec!(3.6)));
+        assert!(split.iter().all(|(a, i)| a + i < dec!(1)));
+    }
+
+    #[test]
+    fn test_rate_below_minus_100_percent_is_rejected() {
+        assert_eq!(normalize_rate(dec!(-150), 12), Err(FinancingError::RateOutOfRange(dec!(-150))));
+        assert_eq!(MonthlyRate::from_annual_percent(dec!(-100)), Err(FinancingError::RateOutOfRange(dec!(-100))));
+        assert_eq!(normalize_annual_interest_rate(dec!(-150)), dec!(-1));
+        assert!(normalize_annual_interest_rate(dec!(-50)) < dec!(0));
+        assert!(normalize_rate(dec!(12), 0).is_err());
+
+        let input = DebtCalculationInput {
+            total_amount: dec!(100000),
+            interest_per_year: dec!(-150),
+            down_payment_percent: dec!(0),
+            total_months: 12,
+            signing_to_first_payment_months: 0,
+        };
+        let error = calculate_debt_trajectory(input).unwrap_err();
+        assert_eq!(error.downcast_ref::<FinancingError>(), Some(&FinancingError::RateOutOfRange(dec!(-150))));
+    }
+
+    #[test]
+    fn test_loan_config_round_trip_and_conversion() {
+        let input = DebtCalculationInput {
+            total_amount: dec!(12000),
+            interest_per_year: dec!(12),
+            down_payment_percent: dec!(10),
+            total_months: 12,
+            signing_to_first_payment_months: 0,
+        };
+        let config = LoanConfig::from(input.clone());
+
+        let json = serde_json::to_string(&config).unwrap();
+        let restored: LoanConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, config);
+
+        let minimal: LoanConfig = serde_json::from_str(
+            r#"{"total_amount":"12000","interest_per_year":"12","total_months":12}"#,
+        ).unwrap();
+        assert_eq!(minimal.down_payment_percent, dec!(0));
+
+        let from_config = calculate(&config).unwrap();
+        let from_input = calculate_debt_trajectory(input).unwrap();
+        assert_eq!(from_config.financed_amount, from_input.financed_amount);
+        assert_eq!(from_config.price_table.total_paid, from_input.price_table.total_paid);
+        assert_eq!(from_config.sac_table.total_paid, from_input.sac_table.total_paid);
+    }
+
+    #[test]
+    fn test_npv() {
+        assert_eq!(npv(&[dec!(110), dec!(121)], dec!(0.1)), dec!(200));
+        assert_eq!(npv(&[], dec!(0.1)), dec!(0));
+
+        // (1 + 1)^1000 does not fit in a Decimal; the discounted stream still converges to 1000.
+        assert_eq!(npv(&[dec!(1000); 1000], dec!(1)).round_dp(6), dec!(1000));
+    }
+
+    #[test]
+    fn test_pv_savings_sac_vs_price() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+        let sac = calculate_sac_table(dec!(12000), rate.into(), 12).unwrap();
+        let price = calculate_price_table(dec!(12000), rate.into(), 12).unwrap();
+
+        // Discounted at the loan's own rate both streams are worth the principal.
+        assert_eq!(pv_savings_sac_vs_price(&sac, &price, rate).round_dp(6), dec!(0));
+        // Without discounting the savings equal the nominal difference.
+        assert!(pv_savings_sac_vs_price(&sac, &price, dec!(0)) > dec!(0));
+        // Discounting at a higher rate favors Price, whose payments come later.
+        assert!(pv_savings_sac_vs_price(&sac, &price, dec!(0.05)) < dec!(0));
+    }
+
+    #[test]
+    fn test_monthly_fee_schedule() {
+        let base = LoanConfig {
+            total_amount: dec!(12000),
+            interest_per_year: dec!(12),
+            total_months: 12,
+            ..Default::default()
+        };
+        // Fee waived for the first three months, then R$ 25 for the rest of the term.
+        let with_fees = LoanConfig {
+            monthly_fees: vec![dec!(0), dec!(0), dec!(0), dec!(25)],
+            ..base.clone()
+        };
+
+        let without = calculate(&base).unwrap();
+        let result = calculate(&with_fees).unwrap();
+
+        let curve = &result.price_table.amortization_curve;
+        assert!(curve[..3].iter().all(|p| p.admin_fee == dec!(0)));
+        assert!(curve[3..].iter().all(|p| p.admin_fee == dec!(25)));
+        assert_eq!(result.price_table.total_paid - without.price_table.total_paid, dec!(225));
+        assert_eq!(result.sac_table.total_paid - without.sac_table.total_paid, dec!(225));
+        assert_eq!(result.sac_table.first_payment, without.sac_table.first_payment);
+        assert_eq!(result.sac_table.last_payment, without.sac_table.last_payment + dec!(25));
+        assert_eq!(result.price_table.fixed_payment, without.price_table.fixed_payment);
+    }
+
+    #[test]
+    fn test_cumulative_cost_crossover() {
+        // A higher rate over a shorter term starts more expensive but ends cheaper.
+        let short = calculate_price_table(dec!(100000), dec!(0.012).into(), 120).unwrap();
+        let long = calculate_price_table(dec!(100000), dec!(0.008).into(), 360).unwrap();
+
+        let month = cumulative_cost_crossover(&short.amortization_curve, &long.amortization_curve).unwrap();
+
+        let cumulative = |curve: &[MonthPayment], n: usize| -> Decimal {
+            curve.iter().take(n).map(MonthPayment::installment).sum()
+        };
+        let m = month as usize;
+        assert!(cumulative(&short.amortization_curve, m - 1) > cumulative(&long.amortization_curve, m - 1));
+        assert!(cumulative(&short.amortization_curve, m) < cumulative(&long.amortization_curve, m));
+
+        assert_eq!(cumulative_cost_crossover(&short.amortization_curve, &short.amortization_curve), None);
+    }
+
+    #[cfg(not(feature = "camel_case"))]
+    #[test]
+    fn test_to_json_value_decimal_representation() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+        let price = calculate_price_table(dec!(12000), rate.into(), 12).unwrap();
+
+        let strings = to_json_value(&price, JsonDecimals::Strings).unwrap();
+        assert_eq!(strings, serde_json::to_value(&price).unwrap());
+        assert_eq!(strings["fixed_payment"], serde_json::json!("1062.74"));
+
+        let numbers = to_json_value(&price, JsonDecimals::RoundedNumbers).unwrap();
+        assert_eq!(numbers["fixed_payment"], serde_json::json!(1062.74));
+        assert_eq!(numbers["total_paid"], serde_json::json!(12752.94));
+        assert!(numbers["amortization_curve"][0]["current_interest"].is_f64());
+
+        let result = calculate(&LoanConfig {
+            total_amount: dec!(12000),
+            interest_per_year: dec!(12),
+            total_months: 12,
+            ..Default::default()
+        })
+        .unwrap();
+        let numbers = to_json_value(&result, JsonDecimals::RoundedNumbers).unwrap();
+        let full_rate = rust_decimal::prelude::ToPrimitive::to_f64(&rate).unwrap();
+        assert_eq!(numbers["monthly_interest_rate"], serde_json::json!(full_rate));
+        assert_ne!(numbers["monthly_interest_rate"], serde_json::json!(0.01));
+
+        // Only Decimal fields are converted; a string that looks like a number stays a string.
+        let labelled = to_json_value(&("1062.74", &price), JsonDecimals::RoundedNumbers).unwrap();
+        assert_eq!(labelled[0], serde_json::json!("1062.74"));
+        assert_eq!(labelled[1]["fixed_payment"], serde_json::json!(1062.74));
+        assert_eq!(serde_json::to_value(&price).unwrap(), strings);
+    }
+
+    #[test]
+    fn test_rate_flip_threshold() {
+        let reinvest_rate = dec!(0.01);
+
+        assert_eq!(rate_flip_threshold(dec!(120000), 120, reinvest_rate), Some(reinvest_rate));
+
+        // A cheaper loan leaves Price ahead once the differences are reinvested; a dearer one favors SAC.
+        let table = |rate: Decimal| {
+            (
+                calculate_sac_table(dec!(120000), rate.into(), 120).unwrap(),
+                calculate_price_table(dec!(120000), rate.into(), 120).unwrap(),
+            )
+        };
+        let (sac, price) = table(dec!(0.008));
+        assert!(pv_savings_sac_vs_price(&sac, &price, reinvest_rate) < dec!(0));
+        let (sac, price) = table(dec!(0.012));
+        assert!(pv_savings_sac_vs_price(&sac, &price, reinvest_rate) > dec!(0));
+        let (sac, price) = table(reinvest_rate);
+        assert!(pv_savings_sac_vs_price(&sac, &price, reinvest_rate).abs() < dec!(0.000001));
+
+        // A single installment is identical in both systems, so nothing flips.
+        assert_eq!(rate_flip_threshold(dec!(1000), 1, reinvest_rate), None);
+        assert_eq!(rate_flip_threshold(dec!(1000), 12, dec!(0)), None);
+        assert_eq!(rate_flip_threshold(dec!(0), 12, reinvest_rate), None);
+    }
+
+    #[test]
+    fn test_sac_inexact_division_final_month() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+        let sac = calculate_sac_table(dec!(200000), rate.into(), 3).unwrap();
+        let curve = &sac.amortization_curve;
+
+        let amortized: Decimal = curve.iter().map(|p| p.current_amortization).sum();
+        assert!((amortized - dec!(200000)).abs() <= dec!(0.01));
+        assert!(curve.iter().all(|p| p.current_interest >= dec!(0)));
+        assert_eq!(curve[2].new_balance, dec!(0));
+        assert_eq!(sac.last_payment, (dec!(200000) / dec!(3) * (dec!(1) + rate)).round_dp(2));
+
+        // The final month amortizes what is left when it is off by more than a cent.
+        assert_eq!(sac_month_amortization(dec!(0), dec!(100), true), dec!(0));
+        assert_eq!(sac_month_amortization(dec!(100.005), dec!(100), true), dec!(100));
+    }
+
+    #[test]
+    fn test_total_paid_with_rate_path() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+        let constant = vec![rate; 12];
+
+        let sac = total_paid_with_rate_path(dec!(12000), 12, &constant, AmortizationSystem::Sac).unwrap();
+        let price = total_paid_with_rate_path(dec!(12000), 12, &[rate], AmortizationSystem::Price).unwrap();
+        assert_eq!(sac, dec!(12740.13));
+        assert_eq!(price, dec!(12752.94));
+
+        let mut rising = constant.clone();
+        rising[6..].iter_mut().for_each(|r| *r = rate * dec!(2));
+        let floating = total_paid_with_rate_path(dec!(12000), 12, &rising, AmortizationSystem::Price).unwrap();
+        assert!(floating > price);
+
+        let free = total_paid_with_rate_path(dec!(12000), 12, &[dec!(0)], AmortizationSystem::Price).unwrap();
+        assert_eq!(free, dec!(12000));
+
+        assert!(total_paid_with_rate_path(dec!(12000), 12, &[], AmortizationSystem::Sac).is_err());
+        assert!(total_paid_with_rate_path(dec!(12000), 0, &constant, AmortizationSystem::Sac).is_err());
+    }
+
+    #[test]
+    fn test_principal_paid_to_date() {
+        let config = LoanConfig {
+            total_amount: dec!(12000),
+            interest_per_year: dec!(12),
+            total_months: 12,
+            monthly_fees: vec![dec!(25)],
+            ..Default::default()
+        };
+        let result = calculate(&config).unwrap();
+
+        for curve in [&result.price_table.amortization_curve, &result.sac_table.amortization_curve] {
+            let mut running = dec!(0);
+            for payment in curve.iter() {
+                running += payment.current_amortization;
+                assert_eq!(payment.principal_paid_to_date.round_dp(10), running.round_dp(10));
+            }
+            assert_eq!(curve.last().unwrap().principal_paid_to_date.round_dp(10), dec!(12000));
+        }
+        assert_eq!(result.sac_table.amortization_curve[0].principal_paid_to_date, dec!(1000));
+
+        let rate = normalize_annual_interest_rate(dec!(12));
+        let full = calculate_price_table(dec!(12000), rate.into(), 12).unwrap();
+        let tail = price_tail(dec!(12000), rate, 12, 3).unwrap();
+        assert_eq!(
+            tail[0].principal_paid_to_date.round_dp(8),
+            full.amortization_curve[9].principal_paid_to_date.round_dp(8)
+        );
+    }
+
+    #[test]
+    fn test_quick_quote_matches_full_tables() {
+        let quote = quick_quote(dec!(12000), dec!(12), 12).unwrap();
+
+        assert_eq!(quote.sac_first, dec!(1113.87));
+        assert_eq!(quote.sac_last, dec!(1009.49));
+        assert_eq!(quote.sac_total, dec!(12740.13));
+        assert_eq!(quote.price_installment, dec!(1062.74));
+        assert_eq!(quote.price_total, dec!(12752.94));
+
+        assert!(quick_quote(dec!(12000), dec!(12), 0).is_err());
+    }
+
+    #[test]
+    fn test_with_present_value_sums_to_principal() {
+        let rate = normalize_annual_interest_rate(dec!(10.5));
+        let price = calculate_price_table(dec!(360000), rate.into(), 420).unwrap();
+        let sac = calculate_sac_table(dec!(360000), rate.into(), 420).unwrap();
+        assert!(price.amortization_curve.iter().all(|p| p.present_value.is_none()));
+
+        for curve in [&price.amortization_curve, &sac.amortization_curve] {
+            let valued = with_present_value(curve, rate);
+            let total: Decimal = valued.iter().map(|p| p.present_value.unwrap()).sum();
+            assert_eq!(total.round_dp(6), dec!(360000));
+            assert_eq!(valued[0].new_balance, curve[0].new_balance);
+        }
+    }
+
+    #[test]
+    fn test_prepayment_for_installment() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+        let price = calculate_price_table(dec!(120000), rate.into(), 120).unwrap();
+
+        let prepayment = prepayment_for_installment(
+            &price.amortization_curve, 24, dec!(1200), AmortizationSystem::Price,
+        ).unwrap();
+        let balance = price.amortization_curve[23].new_balance;
+        let recomputed = calculate_price_table(balance - prepayment, rate.into(), 96).unwrap();
+        assert_eq!(recomputed.fixed_payment, dec!(1200.00));
+
+        let sac = calculate_sac_table(dec!(120000), rate.into(), 120).unwrap();
+        let prepayment = prepayment_for_installment(
+            &sac.amortization_curve, 24, dec!(1000), AmortizationSystem::Sac,
+        ).unwrap();
+        let balance = sac.amortization_curve[23].new_balance;
+        let recomputed = calculate_sac_table(balance - prepayment, rate.into(), 96).unwrap();
+        assert_eq!(recomputed.first_payment, dec!(1000.00));
+
+        let already_lower = prepayment_for_installment(
+            &price.amortization_curve, 24, dec!(5000), AmortizationSystem::Price,
+        ).unwrap();
+        assert_eq!(already_lower, dec!(0));
+        assert!(prepayment_for_installment(&price.amortization_curve, 24, dec!(0), AmortizationSystem::Price).is_err());
+        // The SAC balance after month 24 is 96,000, whose interest alone is about 911 a month.
+        let floor = price.amortization_curve[23].new_balance * rate;
+        assert!(prepayment_for_installment(&price.amortization_curve, 24, floor, AmortizationSystem::Price).is_ok());
+        assert_eq!(
+            prepayment_for_installment(&sac.amortization_curve, 24, dec!(900), AmortizationSystem::Sac),
+            Err(FinancingError::InvalidArgument(
+                "Target installment is below the interest-only floor of the remaining balance.".to_string()
+            ))
+        );
+        assert!(prepayment_for_installment(&price.amortization_curve, 120, dec!(500), AmortizationSystem::Price).is_err());
+    }
+
+    #[test]
+    fn test_settlement_threshold() {
+        let config = LoanConfig {
+            total_amount: dec!(12000),
+            interest_per_year: dec!(12),
+            total_months: 12,
+            ..Default::default()
+        };
+        assert_eq!(config.settlement_threshold, dec!(0.01));
+
+        let result = calculate(&config).unwrap();
+        assert_eq!(result.price_table.settled_at_month, None);
+        assert_eq!(result.price_table.amortization_curve.len(), 12);
+        let last = result.price_table.amortization_curve.last().unwrap();
+        assert_eq!(last.principal_paid_to_date, dec!(12000));
+        assert_eq!(result.price_table.total_paid, dec!(12752.94));
+
+        // A generous threshold settles the final R$ 1000 of SAC principal in month 11.
+        let early = calculate(&LoanConfig { settlement_threshold: dec!(1500), ..config }).unwrap();
+        let curve = &early.sac_table.amortization_curve;
+        assert_eq!(early.sac_table.settled_at_month, Some(11));
+        assert_eq!(curve.len(), 11);
+        assert_eq!(curve[10].current_amortization, dec!(2000));
+        assert_eq!(curve[10].new_balance, dec!(0));
+
+        let parsed: LoanConfig = serde_json::from_str(
+            r#"{"total_amount":"1000","interest_per_year":"10","total_months":10}"#,
+        ).unwrap();
+        assert_eq!(parsed.settlement_threshold, dec!(0.01));
+    }
+
+    #[test]
+    fn test_bimonthly_interest_capitalization() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+        let monthly = calculate_sac_table(dec!(12000), rate.into(), 12).unwrap();
+
+        let same = capitalized_interest_curve(dec!(12000), rate, 12, 1, AmortizationSystem::Sac).unwrap();
+        assert_same_rows(&same, &monthly.amortization_curve);
+
+        let sac = capitalized_interest_curve(dec!(12000), rate, 12, 2, AmortizationSystem::Sac).unwrap();
+        assert_eq!(sac[0].current_interest, dec!(0));
+        assert_eq!(sac[0].current_amortization, dec!(1000));
+        assert_eq!(
+            sac[1].current_interest,
+            monthly.amortization_curve[0].current_interest + monthly.amortization_curve[1].current_interest
+        );
+        let interest = |curve: &[MonthPayment]| -> Decimal { curve.iter().map(|p| p.current_interest).sum() };
+        assert_eq!(interest(&sac).round_dp(10), interest(&monthly.amortization_curve).round_dp(10));
+
+        let price = capitalized_interest_curve(dec!(12000), rate, 12, 2, AmortizationSystem::Price).unwrap();
+        assert_eq!(price[0].current_interest, dec!(0));
+        assert!(price[1].current_interest > dec!(0));
+        assert_eq!(price.last().unwrap().new_balance, dec!(0));
+        assert_eq!(price.last().unwrap().principal_paid_to_date, dec!(12000));
+
+        assert!(capitalized_interest_curve(dec!(12000), rate, 12, 0, AmortizationSystem::Sac).is_err());
+    }
+
+    #[test]
+    fn test_calculate_batch_preserves_order() {
+        let inputs: Vec<DebtCalculationInput> = (1..=20)
+            .map(|months| DebtCalculationInput {
+                total_amount: dec!(12000),
+                interest_per_year: dec!(12),
+                down_payment_percent: dec!(0),
+                total_months: months % 10,
+                signing_to_first_payment_months: 0,
+            })
+            .collect();
+
+        let results = calculate_batch(&inputs);
+
+        assert_eq!(results.len(), inputs.len());
+        for (input, result) in inputs.iter().zip(&results) {
+            match calculate_debt_trajectory(input.clone()) {
+                Ok(expected) => assert_eq!(
+                    result.as_ref().unwrap().price_table.total_paid,
+                    expected.price_table.total_paid
+                ),
+                Err(error) => assert_eq!(result.as_ref().err(), error.downcast_ref::<FinancingError>()),
+            }
+        }
+        assert!(calculate_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_calculate_with_rate_steps() {
+        let stepped = |steps: &[(u32, Decimal)], collar: RateCollar, system: AmortizationSystem| {
+            calculate_with_rate_steps(dec!(12000), 12, steps, collar, system)
+        };
+        let flat = stepped(&[(1, dec!(12))], RateCollar::default(), AmortizationSystem::Price).unwrap();
+        assert_eq!(flat.total_paid, dec!(12752.94));
+        assert_eq!(flat.step_installments[0].installment, dec!(1062.74));
+
+        let steps = [(1, dec!(6)), (7, dec!(12))];
+        let price = stepped(&steps, RateCollar::default(), AmortizationSystem::Price).unwrap();
+        assert_eq!(price.step_installments.len(), 2);
+        assert!(price.step_installments[1].installment > price.step_installments[0].installment);
+        let curve = &price.amortization_curve;
+        assert_eq!(curve[0].installment().round_dp(2), curve[5].installment().round_dp(2));
+        assert_eq!(curve[6].installment().round_dp(2), curve[11].installment().round_dp(2));
+        assert_eq!(curve[11].new_balance, dec!(0));
+
+        let sac = stepped(&steps, RateCollar::default(), AmortizationSystem::Sac).unwrap();
+        assert!(sac.amortization_curve.iter().all(|p| p.current_amortization == dec!(1000)));
+
+        let collar = RateCollar { rate_floor: None, rate_ceiling: Some(dec!(0.008)) };
+        let capped = stepped(&steps, collar, AmortizationSystem::Sac).unwrap();
+        assert_eq!(capped.amortization_curve[0].applied_rate, Some(normalize_annual_interest_rate(dec!(6))));
+        assert_eq!(capped.amortization_curve[6].applied_rate, Some(dec!(0.008)));
+        assert_eq!(capped.amortization_curve[6].current_interest, dec!(6000) * dec!(0.008));
+
+        let unsorted = [(1, dec!(6)), (7, dec!(12)), (5, dec!(8))];
+        assert!(stepped(&unsorted, RateCollar::default(), AmortizationSystem::Sac).is_err());
+        assert!(stepped(&[(2, dec!(6))], RateCollar::default(), AmortizationSystem::Sac).is_err());
+        let inverted = RateCollar { rate_floor: Some(dec!(0.02)), rate_ceiling: Some(dec!(0.01)) };
+        assert!(stepped(&steps, inverted, AmortizationSystem::Sac).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_try_from_json_value() {
+        let input = DebtCalculationInput::try_from(serde_json::json!({
+            "total_amount": 12000,
+            "interest_per_year": "12.5",
+            "total_months": "12",
+        })).unwrap();
+        assert_eq!(input.total_amount, dec!(12000));
+        assert_eq!(input.interest_per_year, dec!(12.5));
+        assert_eq!(input.total_months, 12);
+        assert_eq!(input.down_payment_percent, dec!(0));
+
+        let bad_rate = DebtCalculationInput::try_from(serde_json::json!({
+            "total_amount": 12000,
+            "interest_per_year": "twelve",
+            "total_months": 12,
+        }));
+        assert_eq!(bad_rate.unwrap_err(), FinancingError::InvalidNumber("interest_per_year".to_string()));
+
+        let fractional_months = DebtCalculationInput::try_from(serde_json::json!({
+            "total_amount": 12000,
+            "interest_per_year": 12,
+            "total_months": 12.5,
+        }));
+        assert_eq!(fractional_months.unwrap_err(), FinancingError::InvalidNumber("total_months".to_string()));
+
+        let missing = DebtCalculationInput::try_from(serde_json::json!({ "interest_per_year": 12 }));
+        assert_eq!(missing.unwrap_err(), FinancingError::InvalidNumber("total_amount".to_string()));
+    }
+
+    #[test]
+    fn test_interest_saved_by_prepayment() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+        let original = calculate_price_table(dec!(120000), rate.into(), 120).unwrap().amortization_curve;
+        let balance = original[23].new_balance - dec!(20000);
+
+        // Reduce the installment: same remaining term, lower balance.
+        let mut reduced_installment = original[..24].to_vec();
+        reduced_installment.extend(calculate_price_table(balance, rate.into(), 96).unwrap().amortization_curve);
+        // Reduce the term: the remaining balance repaid over fewer months.
+        let mut reduced_term = original[..24].to_vec();
+        reduced_term.extend(
+            variable_rate_curve(balance, 70, |_| rate, AmortizationSystem::Price)
+        );
+
+        let saved_installment = interest_saved_by_prepayment(&original, &reduced_installment);
+        let saved_term = interest_saved_by_prepayment(&original, &reduced_term);
+        assert!(saved_installment > dec!(0));
+        assert!(saved_term > saved_installment);
+        assert_eq!(interest_saved_by_prepayment(&original, &original), dec!(0));
+    }
+
+    #[test]
+    fn test_calculate_target_last_payment() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+
+        let curve = calculate_target_last_payment(dec!(12000), rate, 12, dec!(900)).unwrap();
+
+        assert_eq!(curve.len(), 12);
+        assert_eq!(curve[11].installment().round_dp(2), dec!(900.00));
+        assert_eq!(curve[11].new_balance, dec!(0));
+        assert!(curve.windows(2).all(|w| w[1].installment() < w[0].installment()));
+        let present_value: Decimal = with_present_value(&curve, rate)
+            .iter()
+            .map(|p| p.present_value.unwrap())
+            .sum();
+        assert_eq!(present_value.round_dp(6), dec!(12000));
+
+        // SAC's own last payment reproduces a SAC-like profile.
+        let sac_like = calculate_target_last_payment(dec!(12000), rate, 12, dec!(1009.49)).unwrap();
+        assert_eq!(sac_like[0].installment().round_dp(0), dec!(1114));
+
+        // Requiring the last payment above the Price installment means increasing payments.
+        assert!(calculate_target_last_payment(dec!(12000), rate, 12, dec!(1100)).is_err());
+        assert!(calculate_target_last_payment(dec!(12000), rate, 12, dec!(-100)).is_err());
+    }
+
+    #[test]
+    fn test_price_term_for_payment() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+        let exact = price_fixed_payment(dec!(12000), rate, 12);
+
+        assert_eq!(price_term_for_payment(dec!(12000), rate, exact).unwrap(), 12);
+        assert_eq!(price_term_for_payment(dec!(12000), rate, dec!(1062.75)).unwrap(), 12);
+        assert_eq!(price_term_for_payment(dec!(12000), rate, dec!(1062.70)).unwrap(), 13);
+        assert_eq!(price_term_for_payment(dec!(12000), dec!(0), dec!(1000)).unwrap(), 12);
+
+        let interest_only = dec!(12000) * rate;
+        assert!(price_term_for_payment(dec!(12000), rate, interest_only).is_err());
+    }
+
+    #[test]
+    fn test_floating_price_negative_amortization_warning() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+
+        let steady = calculate_floating_price(dec!(12000), 12, &[rate]).unwrap();
+        assert!(steady.warnings.is_empty());
+        assert_eq!(steady.fixed_payment, dec!(1062.74));
+        assert_eq!(steady.total_paid, dec!(12752.94));
+
+        // At 10% a month, interest on R$ 100k exceeds the installment set at 1% a month.
+        let path = [dec!(0.01), dec!(0.01), dec!(0.10), dec!(0.10), dec!(0.01)];
+        let result = calculate_floating_price(dec!(100000), 60, &path).unwrap();
+        assert_eq!(
+            result.warnings,
+            vec![
+                CalculationWarning::NegativeAmortization { month: 3 },
+                CalculationWarning::NegativeAmortization { month: 4 },
+            ]
+        );
+        let curve = &result.amortization_curve;
+        assert!(curve[2].new_balance > curve[1].new_balance);
+        assert!(curve[3].new_balance > curve[2].new_balance);
+        assert_eq!(curve[59].new_balance, dec!(0));
+    }
+
+    #[test]
+    fn test_format_brl() {
+        assert_eq!(format_brl(dec!(1062.744)), "R$ 1.062,74");
+        assert_eq!(format_brl(dec!(360000)), "R$ 360.000,00");
+        assert_eq!(format_brl(dec!(0.5)), "R$ 0,50");
+        assert_eq!(format_brl(dec!(-1234567.891)), "-R$ 1.234.567,89");
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+        let price = calculate_price_table(dec!(12000), rate.into(), 12).unwrap();
+
+        let markdown = price.to_markdown();
+        let lines: Vec<&str> = markdown.lines().collect();
+        assert_eq!(lines.len(), 14);
+        assert_eq!(lines[0], "| Month | Installment | Amortization | Interest | Balance |");
+        assert!(lines[2].starts_with("| 1 | R$ 1.062,74 |"));
+        assert!(lines[13].ends_with("| R$ 0,00 |"));
+
+        let truncated = price.to_markdown_truncated(2);
+        let lines: Vec<&str> = truncated.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[4], "| … | … | … | … | … |");
+        assert!(lines[5].starts_with("| 11 |"));
+
+        let sac = calculate_sac_table(dec!(12000), rate.into(), 12).unwrap();
+        assert_eq!(sac.to_markdown_truncated(6), sac.to_markdown());
+    }
+
+    #[test]
+    fn test_monthly_extra_shortens_the_term() {
+        let config = LoanConfig {
+            total_amount: dec!(12000),
+            interest_per_year: dec!(12),
+            total_months: 12,
+            ..Default::default()
+        };
+        let without = calculate(&config).unwrap();
+        let result = calculate(&LoanConfig { monthly_extra: Some(dec!(500)), ..config }).unwrap();
+
+        // SAC amortizes 1500 a month: 8 months pay off 12000.
+        assert_eq!(result.sac_table.settled_at_month, Some(8));
+        assert_eq!(result.sac_table.amortization_curve.len(), 8);
+        assert!(result.sac_table.interest_saved > dec!(0));
+        assert_eq!(
+            result.sac_table.interest_saved,
+            (total_interest(&without.sac_table.amortization_curve)
+                - total_interest(&result.sac_table.amortization_curve)).round_dp(2)
+        );
+
+        let price = &result.price_table;
+        assert!(price.settled_at_month.unwrap() < 12);
+        assert!(price.interest_saved > dec!(0));
+        let last = price.amortization_curve.last().unwrap();
+        assert_eq!(last.new_balance, dec!(0));
+        assert!(last.installment() < price.fixed_payment + dec!(500));
+        assert_eq!(without.price_table.interest_saved, dec!(0));
+    }
+
+    #[test]
+    fn test_blended_rate() {
+        let tranches = [(dec!(300000), dec!(0.008)), (dec!(100000), dec!(0.012))];
+        assert_eq!(blended_rate(&tranches), dec!(0.009));
+        assert_eq!(blended_rate(&[(dec!(5000), dec!(0.01))]), dec!(0.01));
+        assert_eq!(blended_rate(&[]), dec!(0));
+    }
+
+    #[test]
+    fn test_subsidy() {
+        let config = LoanConfig {
+            total_amount: dec!(200000),
+            interest_per_year: dec!(8),
+            total_months: 360,
+            ..Default::default()
+        };
+        let without = calculate(&config).unwrap();
+        assert_eq!(without.subsidy, None);
+
+        let subsidized = LoanConfig {
+            subsidy_upfront: Some(dec!(20000)),
+            subsidy_monthly_rate_reduction: Some(dec!(0.001)),
+            ..config
+        };
+        let result = calculate(&subsidized).unwrap();
+        let rate = normalize_annual_interest_rate(dec!(8));
+        let report = result.subsidy.clone().unwrap();
+
+        assert_eq!(result.financed_amount, dec!(180000));
+        assert_eq!(report.upfront, dec!(20000));
+        assert_eq!(report.effective_monthly_rate, rate - dec!(0.001));
+        assert_eq!(
+            result.sac_table.amortization_curve[0].current_interest,
+            dec!(180000) * (rate - dec!(0.001))
+        );
+        assert!(result.price_table.fixed_payment < without.price_table.fixed_payment);
+    }
+
+    #[test]
+    fn test_yearly_samples() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+        let sac = calculate_sac_table(dec!(30000), rate.into(), 30).unwrap();
+
+        let points = yearly_samples(&sac.amortization_curve);
+
+        assert_eq!(points.len(), 3);
+        assert_eq!((points[0].year, points[0].month), (1, 12));
+        assert_eq!((points[2].year, points[2].month), (3, 30));
+        assert_eq!(points[0].cumulative_amortization, dec!(12000));
+        assert_eq!(points[0].ending_balance, dec!(18000));
+        assert_eq!(points[2].ending_balance, dec!(0));
+        assert_eq!(points[2].cumulative_interest, total_interest(&sac.amortization_curve));
+        assert!(yearly_samples(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_monthly_rate_from_annual_percent() {
+        let rate = MonthlyRate::from_annual_percent(dec!(10.5)).unwrap();
+        assert_eq!(rate.value(), normalize_annual_interest_rate(dec!(10.5)));
+        assert_eq!(MonthlyRate::from(dec!(0.008)).value(), dec!(0.008));
+        assert!(MonthlyRate::new(dec!(-1)).is_err());
+        assert!(MonthlyRate::from_annual_percent(dec!(-100)).is_err());
+
+        let table = calculate_price_table(dec!(12000), rate, 12).unwrap();
+        assert_eq!(table.amortization_curve.len(), 12);
+    }
+
+    #[test]
+    fn test_total_insurance_cost() {
+        let rate = dec!(0.01);
+        let table = calculate_price_table(dec!(100000), rate.into(), 60).unwrap();
+        let mip: Decimal = table
+            .amortization_curve
+            .iter()
+            .map(|month| month.opening_balance() * dec!(0.0003))
+            .sum();
+        let dfi = dec!(60) * dec!(200000) * dec!(0.0001);
+
+        let total = total_insurance_cost(dec!(100000), rate, 60, dec!(0.0003), dec!(0.0001), dec!(200000));
+        assert!((total - (mip + dfi)).abs() <= dec!(0.01));
+        assert_eq!(total_insurance_cost(dec!(100000), rate, 0, dec!(0.0003), dec!(0.0001), dec!(200000)), dec!(0));
+    }
+
+    #[test]
+    fn test_aggregate_portfolio() {
+        let small = calculate_debt_trajectory(DebtCalculationInput {
+            total_amount: dec!(100000),
+            interest_per_year: dec!(10),
+            down_payment_percent: dec!(0),
+            total_months: 120,
+            signing_to_first_payment_months: 0,
+        })
+        .unwrap();
+        let large = calculate_debt_trajectory(DebtCalculationInput {
+            total_amount: dec!(300000),
+            interest_per_year: dec!(10),
+            down_payment_percent: dec!(0),
+            total_months: 120,
+            signing_to_first_payment_months: 0,
+        })
+        .unwrap();
+
+        let stats = aggregate(&[small.clone(), large.clone()]);
+        assert_eq!(stats.loan_count, 2);
+        assert_eq!(stats.sac.total_paid.min, small.sac_table.total_paid);
+        assert_eq!(stats.sac.total_paid.max, large.sac_table.total_paid);
+        assert_eq!(stats.price.first_installment.min, small.price_table.fixed_payment);
+        assert_eq!(
+            stats.price.total_paid.mean,
+            ((small.price_table.total_paid + large.price_table.total_paid) / dec!(2)).round_dp(2)
+        );
+        assert_eq!(aggregate(&[]), PortfolioStats::default());
+    }
+
+    #[test]
+    fn test_calculate_debt_trajectory_with_monthly_rate() {
+        let rate = dec!(0.01);
+        let result = calculate_debt_trajectory_with_monthly_rate(dec!(100000), rate.into(), 120).unwrap();
+        let price = calculate_price_table(dec!(100000), rate.into(), 120).unwrap();
+        let sac = calculate_sac_table(dec!(100000), rate.into(), 120).unwrap();
+
+        assert_eq!(result.financed_amount, dec!(100000));
+        assert_eq!(result.price_table.fixed_payment, price.fixed_payment);
+        assert_eq!(result.sac_table.first_payment, sac.first_payment);
+        assert!(calculate_debt_trajectory_with_monthly_rate(dec!(100000), rate.into(), 0).is_err());
+    }
+
+    #[test]
+    fn test_calculate_quarterly_index_resets() {
+        let index = [dec!(4), dec!(5), dec!(6)];
+        let resets = |index: &[Decimal], collar: RateCollar, system: AmortizationSystem| {
+            calculate_quarterly_index_resets(dec!(120000), 12, index, dec!(3), collar, system)
+        };
+        let result = resets(&index, RateCollar::default(), AmortizationSystem::Price).unwrap();
+        let rates: Vec<Decimal> = result.step_installments.iter().map(|step| step.interest_per_year).collect();
+        assert_eq!(rates, vec![dec!(7), dec!(8), dec!(9), dec!(9)]);
+        assert_eq!(result.step_installments[1].start_month, 4);
+        assert!(result.step_installments[1].installment > result.step_installments[0].installment);
+
+        let sac = resets(&index, RateCollar::default(), AmortizationSystem::Sac).unwrap();
+        assert!(sac.amortization_curve.iter().all(|month| month.current_amortization == dec!(10000)));
+        assert!(resets(&[], RateCollar::default(), AmortizationSystem::Sac).is_err());
+
+        let floor = normalize_annual_interest_rate(dec!(8));
+        let collar = RateCollar { rate_floor: Some(floor), rate_ceiling: None };
+        let floored = resets(&index, collar, AmortizationSystem::Sac).unwrap();
+        let applied: Vec<Decimal> =
+            floored.amortization_curve.iter().map(|month| month.applied_rate.unwrap()).collect();
+        assert!(applied[..6].iter().all(|rate| *rate == floor));
+        assert!(applied[6..].iter().all(|rate| *rate == normalize_annual_interest_rate(dec!(9))));
+    }
+
+    #[test]
+    fn test_recommend_system() {
+        let input = DebtCalculationInput {
+            total_amount: dec!(360000),
+            interest_per_year: dec!(10.5),
+            down_payment_percent: dec!(5),
+            total_months: 420,
+            signing_to_first_payment_months: 0,
+        };
+
+        let (system, _) = recommend_system(input.clone(), BorrowerPreference::LowestFirstPayment).unwrap();
+        assert_eq!(system, AmortizationSystem::Price);
+        let (system, _) = recommend_system(input.clone(), BorrowerPreference::LowestTotalPaid).unwrap();
+        assert_eq!(system, AmortizationSystem::Sac);
+        let (system, result) = recommend_system(input, BorrowerPreference::FastestEquity).unwrap();
+        assert_eq!(system, AmortizationSystem::Sac);
+        assert_eq!(result.financed_amount, dec!(342000));
+    }
+
+    #[test]
+    fn test_infer_principal_from_balance() {
+        let rate = dec!(0.009);
+        for system in [AmortizationSystem::Price, AmortizationSystem::Sac] {
+            let curve = match system {
+                AmortizationSystem::Price => calculate_price_table(dec!(250000), rate.into(), 240).unwrap().amortization_curve,
+                AmortizationSystem::Sac => calculate_sac_table(dec!(250000), rate.into(), 240).unwrap().amortization_curve,
+            };
+            let balance = curve[59].new_balance;
+            assert_eq!(infer_principal_from_balance(balance, 60, rate, 240, system).unwrap(), dec!(250000));
+        }
+
+        assert!(infer_principal_from_balance(dec!(1000), 240, rate, 240, AmortizationSystem::Sac).is_err());
+        assert!(infer_principal_from_balance(dec!(-1), 10, rate, 240, AmortizationSystem::Price).is_err());
+    }
+
+    #[test]
+    fn test_actual365_day_count() {
+        let config = LoanConfig {
+            total_amount: dec!(120000),
+            interest_per_year: dec!(12),
+            total_months: 12,
+            day_count: DayCount::Actual365,
+            first_payment_month: Some(YearMonth { year: 2025, month: 1 }),
+            ..Default::default()
+        };
+        let result = calculate(&config).unwrap();
+
+        let sac = &result.sac_table.amortization_curve;
+        assert_eq!(sac[0].current_interest, dec!(120000) * dec!(0.12) * dec!(31) / dec!(365));
+        assert_eq!(sac[1].current_interest, dec!(110000) * dec!(0.12) * dec!(28) / dec!(365));
+        let price = &result.price_table.amortization_curve;
+        assert!(price[1].current_interest < price[0].current_interest);
+        assert_eq!(price.last().unwrap().new_balance, dec!(0));
+        assert_eq!(YearMonth { year: 2024, month: 2 }.days_in_month(), 29);
+
+        // The installment is solved on the daily accrual, so nothing piles up in the last month.
+        let long = calculate(&LoanConfig {
+            total_amount: dec!(300000),
+            interest_per_year: dec!(10),
+            total_months: 360,
+            ..config.clone()
+        })
+        .unwrap();
+        let price = &long.price_table.amortization_curve;
+        assert_eq!(long.price_table.fixed_payment, price[0].installment().round_dp(2));
+        assert_eq!(price.last().unwrap().installment().round_dp(2), long.price_table.fixed_payment);
+        assert_eq!(long.price_table.amortization_curve.last().unwrap().new_balance, dec!(0));
+        let sac = &long.sac_table.amortization_curve;
+        assert_eq!(sac.last().unwrap().current_amortization.round_dp(2), long.sac_table.fixed_amortization);
+
+        let missing_start = LoanConfig { first_payment_month: None, ..config.clone() };
+        assert!(calculate(&missing_start).is_err());
+        let invalid_start = LoanConfig { first_payment_month: Some(YearMonth { year: 2025, month: 13 }), ..config };
+        assert!(calculate(&invalid_start).is_err());
+    }
+
+    #[test]
+    fn test_trajectory_summary() {
+        let result = calculate_debt_trajectory(DebtCalculationInput {
+            total_amount: dec!(360000),
+            interest_per_year: dec!(10.5),
+            down_payment_percent: dec!(5),
+            total_months: 420,
+            signing_to_first_payment_months: 0,
+        })
+        .unwrap();
+        let summary = result.summary();
+
+        assert_eq!(summary.financed_amount, dec!(342000));
+        assert_eq!(summary.monthly_interest_rate, normalize_annual_interest_rate(dec!(10.5)));
+        assert_eq!(summary.price_fixed_payment, result.price_table.fixed_payment);
+        assert_eq!(summary.sac_last_payment, result.sac_table.last_payment);
+        assert_eq!(summary.sac_total_interest, (result.sac_table.total_paid - dec!(342000)).round_dp(2));
+        assert_eq!(summary.price_total_interest, result.price_table.total_interest);
+        assert_eq!(
+            result.price_table.total_interest,
+            total_interest(&result.price_table.amortization_curve).round_dp(2)
+        );
+    }
+
+    #[test]
+    fn test_calculate_cet_with_upfront_fees() {
+        let rate = dec!(0.01);
+        let no_fees = calculate_cet(dec!(100000), rate, 120, AmortizationSystem::Price, dec!(0), false).unwrap();
+        assert!((no_fees - (dec!(1.01).powu(12) - dec!(1))).abs() < dec!(0.0000001));
+
+        let paid = calculate_cet(dec!(100000), rate, 120, AmortizationSystem::Price, dec!(3000), false).unwrap();
+        let financed = calculate_cet(dec!(100000), rate, 120, AmortizationSystem::Price, dec!(3000), true).unwrap();
+        assert!(paid > no_fees);
+        assert!(financed > no_fees);
+        assert_ne!(paid.round_dp(6), financed.round_dp(6));
+
+        assert!(calculate_cet(dec!(1000), rate, 12, AmortizationSystem::Sac, dec!(1000), false).is_err());
+    }
+
+    #[test]
+    fn test_calculate_consorcio() {
+        let flat = calculate_consorcio(dec!(100000), dec!(15), 100, &[]).unwrap();
+        assert_eq!(flat.amortization_curve[0].installment(), dec!(1150));
+        assert_eq!(flat.total_admin_fee, dec!(15000));
+        assert_eq!(flat.total_paid, dec!(115000));
+        assert_eq!(flat.amortization_curve.last().unwrap().new_balance, dec!(0));
+
+        let indexed = calculate_consorcio(dec!(100000), dec!(15), 100, &[dec!(0), dec!(0.01)]).unwrap();
+        assert_eq!(indexed.amortization_curve[0].installment(), dec!(1150));
+        assert_eq!(indexed.amortization_curve[1].installment(), dec!(1161.5));
+        assert_eq!(indexed.amortization_curve[50].installment(), dec!(1161.5));
+        assert!(calculate_consorcio(dec!(100000), dec!(15), 0, &[]).is_err());
+    }
+
+    #[test]
+    fn test_principal_paid_at_midpoint() {
+        let rate = normalize_annual_interest_rate(dec!(10.5));
+        let sac = calculate_sac_table(dec!(360000), rate.into(), 420).unwrap();
+        let price = calculate_price_table(dec!(360000), rate.into(), 420).unwrap();
+
+        assert_eq!(principal_paid_at_midpoint(&sac.amortization_curve).round_dp(6), dec!(0.5));
+        assert!(principal_paid_at_midpoint(&price.amortization_curve) < dec!(0.2));
+        assert_eq!(principal_paid_at_midpoint(&sac.amortization_curve[..1]), dec!(0));
+    }
+
+    #[test]
+    fn test_result_approx_eq() {
+        let input = DebtCalculationInput {
+            total_amount: dec!(360000),
+            interest_per_year: dec!(10.5),
+            down_payment_percent: dec!(5),
+            total_months: 420,
+            signing_to_first_payment_months: 0,
+        };
+        let result = calculate_debt_trajectory(input.clone()).unwrap();
+        assert_eq!(result, calculate_debt_trajectory(input).unwrap());
+
+        let mut nudged = result.clone();
+        nudged.price_table.amortization_curve[10].current_interest += dec!(0.004);
+        assert_ne!(result, nudged);
+        assert!(result.approx_eq(&nudged, dec!(0.005)));
+        assert!(!result.approx_eq(&nudged, dec!(0.001)));
+
+        nudged.sac_table.amortization_curve.pop();
+        assert!(!result.approx_eq(&nudged, dec!(1)));
+    }
+
+    #[test]
+    fn test_after_tax_schedule() {
+        let sac = calculate_sac_table(dec!(12000), dec!(0.01).into(), 12).unwrap();
+        let after_tax = after_tax_schedule(&sac.amortization_curve, dec!(0.34));
+
+        assert_eq!(after_tax.len(), 12);
+        assert_eq!(after_tax[0], dec!(1000) + dec!(120) * dec!(0.66));
+        assert_eq!(after_tax_schedule(&sac.amortization_curve, dec!(0))[5], sac.amortization_curve[5].installment());
+    }
+
+    #[test]
+    fn test_apply_prepayment_strategies() {
+        let price = calculate_price_table(dec!(100000), dec!(0.01).into(), 120).unwrap().amortization_curve;
+
+        let shorter = apply_prepayment(&price, 12, dec!(20000), PrepaymentStrategy::ReduceTerm, AmortizationSystem::Price).unwrap();
+        assert!(shorter.len() < price.len());
+        assert_eq!(shorter[12].installment().round_dp(2), price[12].installment().round_dp(2));
+        assert_eq!(shorter.last().unwrap().new_balance, dec!(0));
+
+        let lower =
+            apply_prepayment(&price, 12, dec!(20000), PrepaymentStrategy::ReduceInstallment, AmortizationSystem::Price).unwrap();
+        assert_eq!(lower.len(), price.len());
+        assert!(lower[12].installment() < price[12].installment());
+        assert!(interest_saved_by_prepayment(&price, &shorter) > interest_saved_by_prepayment(&price, &lower));
+
+        let payoff = apply_prepayment(&price, 12, dec!(1000000), PrepaymentStrategy::ReduceTerm, AmortizationSystem::Price).unwrap();
+        assert_eq!(payoff.len(), 12);
+        assert!(apply_prepayment(&price, 120, dec!(1000), PrepaymentStrategy::ReduceTerm, AmortizationSystem::Price).is_err());
+    }
+
+    #[test]
+    fn test_best_prepayment_month() {
+        let sac = calculate_sac_table(dec!(100000), dec!(0.01).into(), 120).unwrap().amortization_curve;
+        let (month, saved) =
+            best_prepayment_month(&sac, dec!(10000), 24, PrepaymentStrategy::ReduceTerm, AmortizationSystem::Sac).unwrap();
+        let (_, later_saved) =
+            best_prepayment_month(&sac, dec!(10000), 60, PrepaymentStrategy::ReduceTerm, AmortizationSystem::Sac).unwrap();
+
+        assert_eq!(month, 24);
+        assert!(saved > later_saved);
+        assert!(best_prepayment_month(&sac, dec!(10000), 0, PrepaymentStrategy::ReduceTerm, AmortizationSystem::Sac).is_err());
+    }
+
+    #[test]
+    fn test_annuity_due_price() {
+        let config = LoanConfig {
+            total_amount: dec!(100000),
+            interest_per_year: dec!(12),
+            total_months: 120,
+            ..Default::default()
+        };
+        let ordinary = calculate(&config).unwrap();
+        let explicit_false = calculate(&LoanConfig { annuity_due: false, ..config.clone() }).unwrap();
+        assert_eq!(ordinary, explicit_false);
+
+        let due = calculate(&LoanConfig { annuity_due: true, ..config.clone() }).unwrap();
+        let rate = due.monthly_interest_rate;
+        let expected = price_fixed_payment(dec!(100000), rate, 120) / (dec!(1) + rate);
+        assert_eq!(due.price_table.fixed_payment, expected.round_dp(2));
+        assert!(due.price_table.fixed_payment < ordinary.price_table.fixed_payment);
+        let curve = &due.price_table.amortization_curve;
+        assert_eq!(curve[0].current_interest, dec!(0));
+        assert!((curve.last().unwrap().installment() - expected).abs() < dec!(0.01));
+        assert_eq!(due.sac_table, ordinary.sac_table);
+
+        let daily = LoanConfig {
+            annuity_due: true,
+            day_count: DayCount::Actual365,
+            first_payment_month: Some(YearMonth { year: 2025, month: 1 }),
+            ..config
+        };
+        assert!(calculate(&daily).is_err());
+    }
+
+    #[cfg(feature = "camel_case")]
+    #[test]
+    fn test_camel_case_keys() {
+        let price = calculate_price_table(dec!(12000), dec!(0.01).into(), 12).unwrap();
+        let json = serde_json::to_value(&price).unwrap();
+
+        assert!(json.get("fixedPayment").is_some());
+        assert!(json.get("totalPaid").is_some());
+        assert!(json["amortizationCurve"][0].get("newBalance").is_some());
+        assert!(json.get("fixed_payment").is_none());
+    }
+
+    #[test]
+    fn test_apply_arrears() {
+        let price = calculate_price_table(dec!(100000), dec!(0.01).into(), 120).unwrap().amortization_curve;
+        let late = apply_arrears(&price, 6, dec!(0.02), dec!(0.000333), 30, AmortizationSystem::Price).unwrap();
+
+        let missed = &late[5];
+        assert_eq!(missed.installment(), dec!(0));
+        let charges = (price[5].current_amortization + price[5].current_interest) * (dec!(0.02) + dec!(0.00999));
+        assert_eq!(missed.new_balance, price[5].opening_balance() + price[5].current_interest + charges);
+        assert_eq!(late.len(), price.len());
+        assert!(late[6].installment() > price[6].installment());
+        assert_eq!(late.last().unwrap().new_balance, dec!(0));
+        assert!(apply_arrears(&price, 120, dec!(0.02), dec!(0), 0, AmortizationSystem::Price).is_err());
+    }
+
+    #[test]
+    fn test_split_installment() {
+        assert_eq!(split_installment(dec!(100000), dec!(0.01), dec!(1500)), (dec!(500), dec!(1000)));
+        assert_eq!(split_installment(dec!(100000), dec!(0.01), dec!(800)), (dec!(-200), dec!(1000)));
+        assert_eq!(split_installment(dec!(100000), dec!(0), dec!(800)), (dec!(800), dec!(0)));
+
+        let price = calculate_price_table(dec!(12000), dec!(0.01).into(), 12).unwrap();
+        let first = &price.amortization_curve[0];
+        let (amortization, interest) = split_installment(dec!(12000), dec!(0.01), first.installment());
+        assert_eq!(amortization, first.current_amortization);
+        assert_eq!(interest, first.current_interest);
+    }
+
+    #[test]
+    fn test_marginal_cost_per_year() {
+        let costs = marginal_cost_per_year(dec!(300000), dec!(10), 360, 5, AmortizationSystem::Price).unwrap();
+        let context = RateContext::new(dec!(10)).unwrap();
+        let base = context.price_table(dec!(300000), 360).unwrap().total_paid;
+        let longest = context.price_table(dec!(300000), 420).unwrap().total_paid;
+
+        assert_eq!(costs.len(), 5);
+        assert!(costs.iter().all(|cost| *cost > dec!(0)));
+        assert_eq!(costs.iter().sum::<Decimal>(), longest - base);
+        assert!(marginal_cost_per_year(dec!(300000), dec!(10), 0, 5, AmortizationSystem::Sac).is_err());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_schedule_loops_emit_trace_events() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        struct CountEvents(&'static AtomicUsize);
+
+        impl tracing::Subscriber for CountEvents {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, _: &Attributes<'_>) -> Id {
+                Id::from_u64(1)
+            }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, _: &Event<'_>) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        static EVENTS: AtomicUsize = AtomicUsize::new(0);
+        tracing::subscriber::with_default(CountEvents(&EVENTS), || {
+            calculate_price_table(dec!(12345), dec!(0.01).into(), 12).unwrap();
+            sac_tail(dec!(12345), dec!(0.01), 12, 4).unwrap();
+        });
+        assert_eq!(EVENTS.load(Ordering::Relaxed), 16);
+    }
+
+    #[test]
+    fn test_equivalent_fixed_rate() {
+        let rate = normalize_annual_interest_rate(dec!(12));
+        for system in [AmortizationSystem::Price, AmortizationSystem::Sac] {
+            let fixed = match system {
+                AmortizationSystem::Price => calculate_price_table(dec!(100000), rate.into(), 120).unwrap().amortization_curve,
+                AmortizationSystem::Sac => calculate_sac_table(dec!(100000), rate.into(), 120).unwrap().amortization_curve,
+            };
+            let equivalent = equivalent_fixed_rate(&fixed, dec!(100000), 120, system).unwrap();
+            assert!((equivalent - dec!(0.12)).abs() < dec!(0.000001));
+        }
+
+        let steps = [(1, dec!(9)), (61, dec!(15))];
+        let no_collar = RateCollar::default();
+        let indexed = calculate_with_rate_steps(dec!(100000), 120, &steps, no_collar, AmortizationSystem::Price)
+            .unwrap()
+            .amortization_curve;
+        let equivalent = equivalent_fixed_rate(&indexed, dec!(100000), 120, AmortizationSystem::Price).unwrap();
+        assert!(equivalent > dec!(0.09) && equivalent < dec!(0.15));
+        assert!(equivalent_fixed_rate(&indexed, dec!(100000), 0, AmortizationSystem::Price).is_err());
+    }
+
+    #[test]
+    fn test_loan_builder() {
+        let plain = LoanBuilder::new().principal(dec!(120000)).annual_rate(dec!(12)).months(120);
+        assert_eq!(plain.clone().sac().unwrap(), calculate(&plain.clone().build()).unwrap().sac_table);
+
+        let price = plain.clone().with_grace(6).with_mip(dec!(0.0003)).price().unwrap();
+        let curve = &price.amortization_curve;
+        assert_eq!(curve.len(), 126);
+        assert!(curve[..6].iter().all(|month| month.current_amortization.is_zero()));
+        assert_eq!(curve[0].insurance, dec!(120000) * dec!(0.0003));
+        assert_eq!(curve.last().unwrap().new_balance, dec!(0));
+
+        let prepaid = plain.clone().with_prepayment(12, dec!(30000)).sac().unwrap();
+        assert!(prepaid.amortization_curve.len() < 120);
+        assert!(prepaid.interest_saved > dec!(0));
+        assert_eq!(prepaid.settled_at_month, Some(prepaid.amortization_curve.len() as u32));
+        assert!(plain.with_prepayment(12, dec!(30000)).with_prepayment(115, dec!(1000)).sac().is_err());
+    }
+
+    #[test]
+    fn test_ltv_trajectory() {
+        let sac = calculate_sac_table(dec!(80000), dec!(0.01).into(), 10).unwrap();
+        let flat = ltv_trajectory(&sac.amortization_curve, dec!(100000), dec!(0));
+        assert_eq!(flat.len(), 11);
+        assert_eq!(flat[0], dec!(0.8));
+        assert_eq!(flat[1], dec!(0.72));
+        assert_eq!(*flat.last().unwrap(), dec!(0));
+
+        let appreciating = ltv_trajectory(&sac.amortization_curve, dec!(100000), dec!(0.01));
+        assert_eq!(appreciating[0], dec!(0.8));
+        assert!(appreciating[1] < flat[1]);
+        assert!(ltv_trajectory(&sac.amortization_curve, dec!(0), dec!(0)).is_empty());
+    }
+
+    #[test]
+    fn test_calculate_with_rate_path_collar() {
+        let path = [dec!(0.002), dec!(0.01), dec!(0.03)];
+        let collar = RateCollar { rate_floor: Some(dec!(0.005)), rate_ceiling: Some(dec!(0.015)) };
+        let curve = calculate_with_rate_path(dec!(12000), 12, &path, collar, AmortizationSystem::Sac).unwrap();
+
+        let applied: Vec<Decimal> = curve.iter().take(4).map(|month| month.applied_rate.unwrap()).collect();
+        assert_eq!(applied, vec![dec!(0.005), dec!(0.01), dec!(0.015), dec!(0.015)]);
+        assert_eq!(curve[0].current_interest, dec!(60));
+
+        let unbounded = calculate_with_rate_path(dec!(12000), 12, &path, RateCollar::default(), AmortizationSystem::Sac).unwrap();
+        assert_eq!(unbounded[2].applied_rate, Some(dec!(0.03)));
+
+        let inverted = RateCollar { rate_floor: Some(dec!(0.02)), rate_ceiling: Some(dec!(0.01)) };
+        assert!(calculate_with_rate_path(dec!(12000), 12, &path, inverted, AmortizationSystem::Price).is_err());
+    }
+
+    #[test]
+    fn test_scalar_installments_match_tables() {
+        let rate = normalize_annual_interest_rate(dec!(10.5));
+        let price = calculate_price_table(dec!(342000), rate.into(), 420).unwrap();
+        let sac = calculate_sac_table(dec!(342000), rate.into(), 420).unwrap();
+
+        assert_eq!(price_installment(dec!(342000), rate, 420).round_dp(2), price.fixed_payment);
+        assert_eq!(sac_first_installment(dec!(342000), rate, 420).round_dp(2), sac.first_payment);
+        assert_eq!(sac_last_installment(dec!(342000), rate, 420).round_dp(2), sac.last_payment);
+        assert_eq!(price_installment(dec!(342000), rate, 0), dec!(0));
+    }
+
+    #[test]
+    fn test_interest_ratio() {
+        let rate = normalize_annual_interest_rate(dec!(10));
+        let price = calculate_price_table(dec!(300000), rate.into(), 360).unwrap();
+        assert!(price.interest_ratio() > dec!(1));
+
+        let sac = calculate_sac_table(dec!(12000), dec!(0.01).into(), 12).unwrap();
+        assert_eq!(sac.interest_ratio(), dec!(780) / dec!(12000));
+    }
+
+    #[test]
+    fn test_sac_table_from_first_payment() {
+        let sac = sac_table_from_first_payment(dec!(12000), dec!(1120), 12).unwrap();
+        assert_eq!(sac.first_payment, dec!(1120));
+        assert_eq!(sac.amortization_curve[0].realized_rate(), dec!(0.01));
+        assert_eq!(sac.total_paid, dec!(12780));
+        assert!(sac_table_from_first_payment(dec!(12000), dec!(999), 12).is_err());
+    }
+
+    #[test]
+    fn test_tax_shield_pv() {
+        let price = calculate_price_table(dec!(12000), dec!(0.01).into(), 12).unwrap();
+        let curve = &price.amortization_curve;
+        let undiscounted: Decimal = curve.iter().map(|p| p.current_interest * dec!(0.34)).sum();
+
+        assert_eq!(tax_shield_pv(curve, dec!(0.34), dec!(0)), undiscounted);
+        assert!(tax_shield_pv(curve, dec!(0.34), dec!(0.01)) < undiscounted);
+        assert_eq!(tax_shield_pv(curve, dec!(0), dec!(0.01)), dec!(0));
+    }
+
+    #[test]
+    fn test_to_snapshot_string() {
+        let sac = calculate_sac_table(dec!(3000), dec!(0.01).into(), 3).unwrap();
+        assert_eq!(
+            sac.to_snapshot_string(),
+            "fixed_amortization: 1000.00\nfirst_payment: 1030.00\nlast_payment: 1010.00\ntotal_paid: 3060.00\n\
+             total_interest: 60.00\nsettled_at_month: None\ninterest_saved: 0.00\nupfront_insurance: 0.00\n\
+             month installment amortization interest balance\n\
+             1 1030.00 1000.00 30.00 2000.00\n2 1020.00 1000.00 20.00 1000.00\n3 1010.00 1000.00 10.00 0.00\n"
+        );
+
+        let result = calculate_debt_trajectory_with_monthly_rate(dec!(3000), dec!(0.01).into(), 3).unwrap();
+        let snapshot = result.to_snapshot_string();
+        assert!(snapshot.starts_with("financed_amount: 3000.00\nmonthly_interest_rate: 0.0100000000\n"));
+        assert!(snapshot.contains("subsidy: none\ngrace_transition: None\n[price]\n"));
+        assert!(snapshot.contains("[price]\nfixed_payment: 1020.07\ntotal_paid: 3060.20\ntotal_interest: 60.20\n"));
+        assert!(snapshot.ends_with(&sac.to_snapshot_string()));
+
+        let config = LoanConfig {
+            total_amount: dec!(3000),
+            interest_per_year: dec!(12),
+            total_months: 3,
+            grace_months: 1,
+            subsidy_monthly_rate_reduction: Some(dec!(0.002)),
+            ..Default::default()
+        };
+        let snapshot = calculate(&config).unwrap().to_snapshot_string();
+        assert!(snapshot.contains(
+            "monthly_rate_reduction 0.0020000000 effective_monthly_rate 0.0074887926\n\
+             grace_transition: Some(PayInLastGraceMonth)\n"
+        ));
+    }
+
+    #[test]
+    fn test_verify_sac_last_payment() {
+        let rate = normalize_annual_interest_rate(dec!(10.5));
+        let sac = calculate_sac_table(dec!(342000), rate.into(), 420).unwrap();
+        assert!(verify_sac_last_payment(&sac, rate));
+
+        let mut broken = sac.clone();
+        broken.last_payment += dec!(5);
+        assert!(!verify_sac_last_payment(&broken, rate));
+    }
+
+    #[test]
+    fn test_cap_term() {
+        let within = cap_term(dec!(100000), dec!(0.01), dec!(1500), 420, AmortizationSystem::Price).unwrap();
+        assert!(!within.capped);
+        assert_eq!(within.total_months, price_term_for_payment(dec!(100000), dec!(0.01), dec!(1500)).unwrap());
+        assert!(within.first_installment <= dec!(1500));
+
+        let capped = cap_term(dec!(100000), dec!(0.01), dec!(1100), 60, AmortizationSystem::Price).unwrap();
+        assert!(capped.capped);
+        assert_eq!(capped.total_months, 60);
+        assert!(capped.first_installment > dec!(1100));
+
+        let sac = cap_term(dec!(100000), dec!(0.01), dec!(1500), 420, AmortizationSystem::Sac).unwrap();
+        assert_eq!((sac.total_months, sac.capped), (200, false));
+        let never = cap_term(dec!(100000), dec!(0.01), dec!(900), 420, AmortizationSystem::Sac).unwrap();
+        assert_eq!((never.total_months, never.capped), (420, true));
+        assert!(cap_term(dec!(100000), dec!(0.01), dec!(1500), 0, AmortizationSystem::Sac).is_err());
+    }
+
+    #[test]
+    fn test_points_break_even() {
+        let month = points_break_even(dec!(100000), 120, dec!(0.01), dec!(0.009), dec!(1500), AmortizationSystem::Sac)
+            .unwrap()
+            .unwrap();
+        // SAC saves 0.001 * balance each month: 100 + 99.17 + ... reaches 1500 in month 16.
+        assert_eq!(month, 16);
+
+        let never = points_break_even(dec!(100000), 12, dec!(0.01), dec!(0.009), dec!(50000), AmortizationSystem::Price).unwrap();
+        assert_eq!(never, None);
+        assert!(points_break_even(dec!(100000), 0, dec!(0.01), dec!(0.009), dec!(1), AmortizationSystem::Price).is_err());
+    }
+
+    #[test]
+    fn test_balance_curve_matches_schedule() {
+        let rate = normalize_annual_interest_rate(dec!(10.5));
+        let price = calculate_price_table(dec!(342000), rate.into(), 420).unwrap();
+        let sac = calculate_sac_table(dec!(342000), rate.into(), 420).unwrap();
+        let balances = |curve: &[MonthPayment]| curve.iter().map(|p| p.new_balance).collect::<Vec<_>>();
+
+        assert_eq!(balance_curve(dec!(342000), rate, 420, AmortizationSystem::Price), balances(&price.amortization_curve));
+        assert_eq!(balance_curve(dec!(342000), rate, 420, AmortizationSystem::Sac), balances(&sac.amortization_curve));
+        assert!(balance_curve(dec!(342000), rate, 0, AmortizationSystem::Sac).is_empty());
+    }
+
+    #[test]
+    fn test_round_installment_to() {
+        let config = LoanConfig {
+            total_amount: dec!(100000),
+            interest_per_year: dec!(12),
+            total_months: 360,
+            ..Default::default()
+        };
+        let plain = calculate(&config).unwrap();
+        assert_eq!(plain, calculate(&LoanConfig { round_installment_to: None, ..config.clone() }).unwrap());
+
+        let rounded = calculate(&LoanConfig { round_installment_to: Some(dec!(10)), ..config }).unwrap();
+        let price = &rounded.price_table;
+        assert_eq!(price.fixed_payment, (plain.price_table.fixed_payment / dec!(10)).ceil() * dec!(10));
+        assert_eq!(price.amortization_curve[0].installment().round_dp(2), price.fixed_payment);
+        assert!(price.settled_at_month.unwrap() < 360);
+        assert!(price.interest_saved > dec!(0));
+        assert_eq!(rounded.sac_table, plain.sac_table);
+    }
+
+    #[test]
+    fn test_calculate_reverse_mortgage() {
+        let reverse = calculate_reverse_mortgage(dec!(10000), dec!(1000), dec!(0.01), 2).unwrap();
+        let curve = &reverse.amortization_curve;
+
+        assert_eq!(curve[0].new_balance, dec!(11100));
+        assert_eq!(curve[1].new_balance, dec!(12211));
+        assert!(curve.iter().all(|month| month.current_amortization < dec!(0)));
+        assert_eq!(curve[1].installment(), dec!(-1000));
+        assert_eq!(reverse.final_balance, dec!(12211));
+        assert_eq!(reverse.total_drawn, dec!(12000));
+        assert!(calculate_reverse_mortgage(dec!(10000), dec!(1000), dec!(0.01), 0).is_err());
+    }
+
+    #[test]
+    fn test_total_paid_real() {
+        let rate = normalize_annual_interest_rate(dec!(10));
+        let price = calculate_price_table(dec!(300000), rate.into(), 360).unwrap();
+
+        assert_eq!(total_paid_real(&price.amortization_curve, dec!(0)), price.total_paid);
+        assert!(total_paid_real(&price.amortization_curve, dec!(0.004)) < price.total_paid * dec!(0.6));
+        assert_eq!(total_paid_real(&price.amortization_curve, rate).round_dp(0), dec!(300000));
+    }
+
+    #[test]
+    fn test_seasonal_weights() {
+        let config = LoanConfig {
+            total_amount: dec!(120000),
+            interest_per_year: dec!(12),
+            total_months: 24,
+            first_payment_month: Some(YearMonth { year: 2025, month: 1 }),
+            ..Default::default()
+        };
+        let plain = calculate(&config).unwrap();
+        let uniform = calculate(&LoanConfig { seasonal_weights: vec![dec!(1); 12], ..config.clone() }).unwrap();
+        assert_eq!(uniform, plain);
+
+        let mut weights = vec![dec!(0.5); 6];
+        weights.extend(vec![dec!(1.5); 6]);
+        let seasonal = calculate(&LoanConfig { seasonal_weights: weights, ..config.clone() }).unwrap();
+        let curve = &seasonal.price_table.amortization_curve;
+        assert_eq!((curve[6].installment() / curve[0].installment()).round_dp(6), dec!(3));
+        assert!((curve[23].installment() - curve[11].installment()).abs() < dec!(0.01));
+        assert_eq!(curve.last().unwrap().new_balance, dec!(0));
+
+        let invalid = LoanConfig { seasonal_weights: vec![dec!(2); 12], ..config.clone() };
+        assert!(calculate(&invalid).is_err());
+        let short = LoanConfig { seasonal_weights: vec![dec!(1); 5], ..config.clone() };
+        assert!(calculate(&short).is_err());
+
+        let mut december = vec![dec!(0); 11];
+        december.push(dec!(12));
+        let zero_term = LoanConfig { seasonal_weights: december, total_months: 3, ..config.clone() };
+        assert!(calculate(&zero_term).is_err());
+        let seasonal = [dec!(0.5), dec!(1.5)].repeat(6);
+        let daily = LoanConfig { seasonal_weights: seasonal.clone(), day_count: DayCount::Actual365, ..config.clone() };
+        assert!(calculate(&daily).is_err());
+        assert!(calculate(&LoanConfig { seasonal_weights: seasonal, annuity_due: true, ..config }).is_err());
+    }
+
+    #[test]
+    fn test_effective_rate_curve() {
+        let sac = calculate_sac_table(dec!(12000), dec!(0.01).into(), 12).unwrap();
+        assert!(effective_rate_curve(&sac.amortization_curve).iter().all(|rate| *rate == dec!(0.01)));
+
+        let steps = [(1, dec!(6)), (7, dec!(12))];
+        let stepped =
+            calculate_with_rate_steps(dec!(12000), 12, &steps, RateCollar::default(), AmortizationSystem::Sac).unwrap();
+        let rates = effective_rate_curve(&stepped.amortization_curve);
+        assert_eq!(rates[0], normalize_annual_interest_rate(dec!(6)));
+        assert_eq!(rates[11], normalize_annual_interest_rate(dec!(12)));
+
+        let mut settled = sac.amortization_curve.clone();
+        settled[11].new_balance = dec!(0);
+        settled[11].current_amortization = dec!(0);
+        assert_eq!(effective_rate_curve(&settled)[11], dec!(0));
+    }
+
+    #[test]
+    fn test_calculate_mixed() {
+        let sac = calculate_sac_table(dec!(60000), dec!(0.01).into(), 120).unwrap();
+        let price = calculate_price_table(dec!(40000), dec!(0.01).into(), 120).unwrap();
+        let mixed = calculate_mixed(dec!(100000), dec!(0.6), dec!(0.01), 120).unwrap();
+
+        assert_eq!(mixed.amortization_curve.len(), 120);
+        assert_eq!(mixed.total_paid, (sac.total_paid + price.total_paid).round_dp(2));
+        assert_eq!(mixed.first_payment, (sac.amortization_curve[0].installment() + price.amortization_curve[0].installment()).round_dp(2));
+        assert_eq!(mixed.amortization_curve.last().unwrap().new_balance, dec!(0));
+
+        let all_sac = calculate_mixed(dec!(60000), dec!(1), dec!(0.01), 120).unwrap();
+        assert_eq!(all_sac.total_paid, sac.total_paid);
+        assert!(calculate_mixed(dec!(100000), dec!(1.5), dec!(0.01), 120).is_err());
+    }
+
+    #[test]
+    fn test_savings_plan_for_down_payment() {
+        assert_eq!(savings_plan_for_down_payment(dec!(12000), 12, dec!(0)).unwrap(), dec!(1000));
+
+        let deposit = savings_plan_for_down_payment(dec!(50000), 24, dec!(0.008)).unwrap();
+        let mut saved = dec!(0);
+        for _ in 0..24 {
+            saved = saved * dec!(1.008) + deposit;
+        }
+        assert!((saved - dec!(50000)).abs() < dec!(1));
+        assert!(savings_plan_for_down_payment(dec!(50000), 0, dec!(0.008)).is_err());
+        assert!(savings_plan_for_down_payment(dec!(50000), 12, dec!(-1)).is_err());
+
+        assert_eq!(savings_plan_for_down_payment(dec!(50000), 1000, dec!(0.1)).unwrap(), dec!(0));
+        let negative = savings_plan_for_down_payment(dec!(12000), 12, dec!(-0.01)).unwrap();
+        assert!(negative > dec!(1000));
+    }
+
+    #[test]
+    fn test_tail_total() {
+        let sac = calculate_sac_table(dec!(12000), dec!(0.01).into(), 12).unwrap();
+        let curve = &sac.amortization_curve;
+
+        assert_eq!(tail_total(curve, 2), dec!(1020) + dec!(1010));
+        assert_eq!(tail_total(curve, 100), curve.iter().map(MonthPayment::installment).sum::<Decimal>());
+        assert_eq!(tail_total(curve, 0), dec!(0));
+    }
+
+    #[test]
+    fn test_grace_transition_residual() {
+        let config = LoanConfig {
+            total_amount: dec!(100000),
+            interest_per_year: dec!(10.5),
+            total_months: 120,
+            grace_months: 6,
+            ..Default::default()
+        };
+        let paid = calculate(&config).unwrap();
+        let rolled = calculate(&LoanConfig { grace_transition: GraceTransition::RollIntoFirstInstallment, ..config }).unwrap();
+        assert_eq!(paid.grace_transition, Some(GraceTransition::PayInLastGraceMonth));
+        assert_eq!(rolled.grace_transition, Some(GraceTransition::RollIntoFirstInstallment));
+
+        let exact_interest = dec!(100000) * paid.monthly_interest_rate * dec!(6);
+        let grace_interest = |curve: &[MonthPayment]| curve[..6].iter().map(|p| p.current_interest).sum::<Decimal>();
+        let paid_curve = &paid.sac_table.amortization_curve;
+        let rolled_curve = &rolled.sac_table.amortization_curve;
+        assert!(paid_curve[..6].iter().all(|p| p.current_interest == p.current_interest.round_dp(2)));
+        assert_eq!(grace_interest(paid_curve), exact_interest.round_dp(2));
+        assert!(rolled_curve[..6].iter().all(|p| p.current_interest == p.current_interest.round_dp(2)));
+        assert_eq!(grace_interest(rolled_curve) + rolled_curve[6].accrued_interest, exact_interest);
+        assert_eq!(paid_curve[6].accrued_interest, dec!(0));
+
+        let no_grace = calculate_debt_trajectory_with_monthly_rate(dec!(1000), dec!(0.01).into(), 12).unwrap();
+        assert_eq!(no_grace.grace_transition, None);
+    }
+
+    #[test]
+    fn test_insurance_upfront() {
+        let config = LoanConfig {
+            total_amount: dec!(120000),
+            interest_per_year: dec!(12),
+            total_months: 120,
+            mip_rate: Some(dec!(0.0003)),
+            ..Default::default()
+        };
+        let monthly = calculate(&config).unwrap();
+        let upfront = calculate(&LoanConfig { insurance_upfront: true, ..config.clone() }).unwrap();
+
+        let monthly_insurance: Decimal = monthly.sac_table.amortization_curve.iter().map(|p| p.insurance).sum();
+        assert_eq!(upfront.sac_table.upfront_insurance, monthly_insurance.round_dp(2));
+        assert!(upfront.price_table.upfront_insurance > upfront.sac_table.upfront_insurance);
+        assert!(upfront.sac_table.amortization_curve.iter().all(|p| p.insurance.is_zero()));
+        assert_eq!(monthly.sac_table.upfront_insurance, dec!(0));
+        assert!(upfront.sac_table.total_paid < monthly.sac_table.total_paid);
+
+        let financed_config = LoanConfig { insurance_upfront: true, finance_insurance_upfront: true, ..config.clone() };
+        let financed = calculate(&financed_config).unwrap();
+        let sac_premium = upfront.sac_table.upfront_insurance;
+        assert_eq!(financed.financed_amount, dec!(120000));
+        assert_eq!(financed.sac_table.upfront_insurance, sac_premium);
+        assert_eq!(financed.price_table.upfront_insurance, upfront.price_table.upfront_insurance);
+        assert_eq!(financed.sac_table.amortization_curve[0].opening_balance(), dec!(120000) + sac_premium);
+        let sac_amortization = (dec!(120000) + sac_premium) / dec!(120);
+        assert_eq!(financed.sac_table.amortization_curve[0].current_amortization, sac_amortization);
+        assert!(financed.sac_table.amortization_curve.iter().all(|p| p.insurance.is_zero()));
+        assert!(financed.sac_table.amortization_curve.last().unwrap().new_balance.is_zero());
+        let paid_at_signing = upfront.price_table.total_paid + upfront.price_table.upfront_insurance;
+        assert!(financed.price_table.total_paid > paid_at_signing);
+
+        assert!(calculate(&LoanConfig { finance_insurance_upfront: true, ..config.clone() }).is_err());
+        assert!(calculate(&LoanConfig { mip_rate: None, ..financed_config }).is_err());
+    }
+
+    #[test]
+    fn test_to_currency() {
+        let result = calculate_debt_trajectory(DebtCalculationInput {
+            total_amount: dec!(100000),
+            interest_per_year: dec!(12),
+            down_payment_percent: dec!(0),
+            total_months: 12,
+            signing_to_first_payment_months: 0,
+        })
+        .unwrap();
+        let converted = result.to_currency(dec!(0.2));
+
+        assert_eq!(converted.financed_amount, result.financed_amount * dec!(0.2));
+        assert_eq!(converted.monthly_interest_rate, result.monthly_interest_rate);
+        assert_eq!(converted.price_table.fixed_payment, result.price_table.fixed_payment * dec!(0.2));
+        assert_eq!(converted.sac_table.total_paid, result.sac_table.total_paid * dec!(0.2));
+        assert_eq!(converted.sac_table.amortization_curve.len(), 12);
+        assert_eq!(
+            converted.sac_table.amortization_curve[0].new_balance,
+            result.sac_table.amortization_curve[0].new_balance * dec!(0.2)
+        );
+        assert_eq!(result.to_currency(dec!(1)), result);
+    }
+
+    #[test]
+    fn test_amortizes_faster_than() {
+        let sac = calculate_sac_table(dec!(120000), dec!(0.01).into(), 120).unwrap();
+        let price = calculate_price_table(dec!(120000), dec!(0.01).into(), 120).unwrap();
+        let price_fractions: Vec<Decimal> =
+            price.amortization_curve.iter().map(|month| month.principal_paid_to_date / dec!(120000)).collect();
+
+        assert!(amortizes_faster_than(&sac.amortization_curve, &price_fractions));
+        let ahead_of_price: Vec<Decimal> = price_fractions[..6].iter().map(|fraction| fraction + dec!(0.01)).collect();
+        assert!(!amortizes_faster_than(&price.amortization_curve, &ahead_of_price));
+        assert!(amortizes_faster_than(&sac.amortization_curve[..12], &price_fractions));
+        assert!(!amortizes_faster_than(&sac.amortization_curve, &[]));
+    }
+
+    #[test]
+    fn test_total_paid_with_annual_prepayment() {
+        let input = DebtCalculationInput {
+            total_amount: dec!(100000),
+            interest_per_year: dec!(12),
+            down_payment_percent: dec!(0),
+            total_months: 120,
+            signing_to_first_payment_months: 0,
+        };
+        let base = calculate_debt_trajectory(input.clone()).unwrap();
+
+        let (total_paid, payoff_month) = total_paid_with_annual_prepayment(
+            input.clone(),
+            dec!(10000),
+            PrepaymentStrategy::ReduceTerm,
+            AmortizationSystem::Sac,
+        )
+        .unwrap();
+        // 81550.24 in installments plus the five lump sums paid before month 61.
+        assert_eq!(payoff_month, 61);
+        assert_eq!(total_paid, dec!(131550.24));
+        assert!(total_paid < base.sac_table.total_paid);
+
+        let (total_paid, payoff_month) = total_paid_with_annual_prepayment(
+            input.clone(),
+            dec!(0),
+            PrepaymentStrategy::ReduceInstallment,
+            AmortizationSystem::Price,
+        )
+        .unwrap();
+        assert_eq!(payoff_month, 120);
+        assert_eq!(total_paid, base.price_table.total_paid);
+
+        assert!(
+            total_paid_with_annual_prepayment(input, dec!(-1), PrepaymentStrategy::ReduceTerm, AmortizationSystem::Sac)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_month_payment_new() {
+        let payment = MonthPayment::new(dec!(900), dec!(100), dec!(10));
+
+        assert_eq!(payment.installment(), dec!(110));
+        assert_eq!(payment.opening_balance(), dec!(1000));
+        assert_eq!(payment.principal_paid_to_date, dec!(0));
+        assert_eq!(payment.present_value, None);
+        assert_eq!(payment.applied_rate, None);
+    }
+
+    #[test]
+    fn test_interest_at_month() {
+        let rate = dec!(0.0085);
+        let price = calculate_price_table(dec!(300000), rate.into(), 360).unwrap();
+        let sac = calculate_sac_table(dec!(300000), rate.into(), 360).unwrap();
+
+        for month in [1, 2, 120, 359, 360] {
+            let price_interest = interest_at_month(dec!(300000), rate, 360, AmortizationSystem::Price, month);
+            let sac_interest = interest_at_month(dec!(300000), rate, 360, AmortizationSystem::Sac, month);
+            let index = month as usize - 1;
+            assert!((price_interest - price.amortization_curve[index].current_interest).abs() < dec!(0.0000001));
+            assert!((sac_interest - sac.amortization_curve[index].current_interest).abs() < dec!(0.0000001));
+        }
+        assert_eq!(interest_at_month(dec!(300000), rate, 360, AmortizationSystem::Sac, 0), dec!(0));
+        assert_eq!(interest_at_month(dec!(300000), rate, 360, AmortizationSystem::Price, 361), dec!(0));
+    }
+
+    #[test]
+    fn test_final_stub_fraction() {
+        let config = LoanConfig {
+            total_amount: dec!(100000),
+            interest_per_year: dec!(12),
+            total_months: 24,
+            ..Default::default()
+        };
+        let full = calculate(&config).unwrap();
+        let stub = calculate(&LoanConfig { final_stub_fraction: Some(dec!(0.5)), ..config.clone() }).unwrap();
+
+        let full_last = full.sac_table.amortization_curve.last().unwrap();
+        let stub_last = stub.sac_table.amortization_curve.last().unwrap();
+        assert!(stub_last.current_interest < full_last.current_interest);
+        assert!(stub_last.current_interest > full_last.current_interest / dec!(2) - dec!(1));
+        assert_eq!(stub_last.new_balance, dec!(0));
+        assert!(stub.sac_table.last_payment < full.sac_table.last_payment);
+        assert!(stub.price_table.total_paid < full.price_table.total_paid);
+        assert_eq!(stub.price_table.fixed_payment, full.price_table.fixed_payment);
+
+        assert!(calculate(&LoanConfig { final_stub_fraction: Some(dec!(1)), ..config }).is_err());
+    }
+
+    #[test]
+    fn test_extra_payments_keep_adjusted_curves() {
+        let config = LoanConfig {
+            total_amount: dec!(120000),
+            interest_per_year: dec!(12),
+            total_months: 24,
+            first_payment_month: Some(YearMonth { year: 2025, month: 1 }),
+            ..Default::default()
+        };
+
+        let annuity_due =
+            calculate(&LoanConfig { annuity_due: true, monthly_extra: Some(dec!(500)), ..config.clone() }).unwrap();
+        let curve = &annuity_due.price_table.amortization_curve;
+        assert_eq!(curve[0].current_interest, dec!(0));
+        assert!(curve.len() < 24);
+
+        let actual = LoanConfig { day_count: DayCount::Actual365, ..config.clone() };
+        let extra = calculate(&LoanConfig { min_first_installment: Some(dec!(7000)), ..actual }).unwrap();
+        let sac = &extra.sac_table.amortization_curve;
+        let day_interest =
+            |balance: Decimal, days: u32| (balance * dec!(0.12) * Decimal::from(days) / dec!(365)).round_dp(10);
+        assert_eq!(sac[0].current_interest.round_dp(10), day_interest(dec!(120000), 31));
+        assert_eq!(sac[1].current_interest.round_dp(10), day_interest(sac[0].new_balance, 28));
+        assert_eq!(sac[0].installment().round_dp(2), dec!(7000));
+
+        let stub = LoanConfig {
+            final_stub_fraction: Some(dec!(0.5)),
+            prepayments: vec![(6, dec!(10000))],
+            prepayment_strategy: PrepaymentStrategy::ReduceInstallment,
+            ..config.clone()
+        };
+        let full = calculate(&LoanConfig { final_stub_fraction: None, ..stub.clone() }).unwrap();
+        let stubbed = calculate(&stub).unwrap();
+        let last_interest =
+            |result: &DebtTrajectoryResult| result.sac_table.amortization_curve.last().unwrap().current_interest;
+        assert!(last_interest(&stubbed) < last_interest(&full));
+
+        let seasonal = LoanConfig { seasonal_weights: [dec!(1.5), dec!(0.5)].repeat(6), ..config };
+        assert!(calculate(&LoanConfig { round_installment_to: Some(dec!(10)), ..seasonal }).is_err());
+    }
+
+    #[test]
+    fn test_systems_are_equivalent() {
+        let sac = calculate_sac_table(dec!(1000), dec!(0.0001).into(), 2).unwrap();
+        let price = calculate_price_table(dec!(1000), dec!(0.0001).into(), 2).unwrap();
+        assert!(systems_are_equivalent(&sac, &price, dec!(0.10)));
+
+        let sac = calculate_sac_table(dec!(100000), dec!(0.01).into(), 120).unwrap();
+        let price = calculate_price_table(dec!(100000), dec!(0.01).into(), 120).unwrap();
+        assert!(!systems_are_equivalent(&sac, &price, dec!(1)));
+    }
+
+    #[test]
+    fn test_min_first_installment() {
+        let config = LoanConfig {
+            total_amount: dec!(100000),
+            interest_per_year: dec!(12),
+            total_months: 360,
+            ..Default::default()
+        };
+        let plain = calculate(&config).unwrap();
+        let result = calculate(&LoanConfig { min_first_installment: Some(dec!(2000)), ..config.clone() }).unwrap();
+
+        assert_eq!(result.price_table.fixed_payment, dec!(2000));
+        assert!(result.price_table.settled_at_month.unwrap() < 360);
+        assert_eq!(result.sac_table.amortization_curve[0].installment().round_dp(2), dec!(2000));
+        assert!(result.sac_table.settled_at_month.unwrap() < 360);
+
+        let below = calculate(&LoanConfig { min_first_installment: Some(dec!(100)), ..config.clone() }).unwrap();
+        assert_eq!(below, plain);
+
+        assert!(calculate(&LoanConfig { min_first_installment: Some(dec!(200000)), ..config }).is_err());
+    }
+
+    #[test]
+    fn test_compound_interest_excess() {
+        let price = calculate_price_table(dec!(100000), dec!(0.01).into(), 120).unwrap();
+        let sac = calculate_sac_table(dec!(100000), dec!(0.01).into(), 120).unwrap();
+        let price_excess = compound_interest_excess(&price.amortization_curve, dec!(0.01));
+        let sac_excess = compound_interest_excess(&sac.amortization_curve, dec!(0.01));
+
+        assert!(price_excess > dec!(0));
+        assert!(sac_excess > dec!(0));
+        assert!(price_excess > sac_excess);
+        assert!(price_excess < total_interest(&price.amortization_curve));
+
+        let short = calculate_price_table(dec!(1000), dec!(0.01).into(), 1).unwrap();
+        assert_eq!(compound_interest_excess(&short.amortization_curve, dec!(0.01)), dec!(0));
+        assert_eq!(compound_interest_excess(&[], dec!(0.01)), dec!(0));
+    }
+
+    #[test]
+    fn test_schedule_arrays() {
+        let rate = MonthlyRate::from(dec!(0.01));
+        let sac: [MonthPayment; 24] = sac_schedule_array(dec!(100000), rate);
+        let price = price_schedule_array::<24>(dec!(100000), rate);
+
+        assert_eq!(sac.to_vec(), calculate_sac_table(dec!(100000), rate, 24).unwrap().amortization_curve);
+        assert_eq!(price.to_vec(), calculate_price_table(dec!(100000), rate, 24).unwrap().amortization_curve);
+    }
+
+    #[test]
+    fn test_rounded_installments_carry_forward() {
+        let sac = calculate_sac_table(dec!(100000), dec!(0.00797414).into(), 360).unwrap();
+        let unrounded_total: Decimal = sac.amortization_curve.iter().map(MonthPayment::installment).sum();
+
+        let carried = rounded_installments(&sac.amortization_curve, RoundingPolicy::CarryForward);
+        assert_eq!(carried.len(), 360);
+        assert_eq!(carried.iter().sum::<Decimal>(), unrounded_total.round_dp(2));
+        assert!(carried.iter().all(|installment| installment.scale() <= 2));
+
+        let per_month = rounded_installments(&sac.amortization_curve, RoundingPolicy::PerMonth);
+        assert_eq!(per_month[0], sac.amortization_curve[0].installment().round_dp(2));
+    }
+
+    #[test]
+    fn test_interest_share_curve() {
+        let sac = calculate_sac_table(dec!(120000), dec!(0.01).into(), 120).unwrap();
+        let shares = interest_share_curve(&sac);
+
+        assert_eq!(shares.len(), 120);
+        assert_eq!(shares[0], dec!(1200) / dec!(2200));
+        assert!(shares.windows(2).all(|pair| pair[1] < pair[0]));
+    }
+
+    #[test]
+    fn test_employer_subsidy_monthly() {
+        let config = LoanConfig {
+            total_amount: dec!(120000),
+            interest_per_year: dec!(12),
+            total_months: 120,
+            ..Default::default()
+        };
+        let gross = calculate(&config).unwrap();
+        let result = calculate(&LoanConfig { employer_subsidy_monthly: Some(dec!(1100)), ..config.clone() }).unwrap();
+
+        assert_eq!(result.sac_table.total_paid, gross.sac_table.total_paid);
+        let sac = &result.sac_table.amortization_curve;
+        assert_eq!(sac[0].employer_subsidy, dec!(1100));
+        assert_eq!(sac[0].net_installment(), sac[0].installment() - dec!(1100));
+        let last = sac.last().unwrap();
+        assert!(last.installment() < dec!(1100));
+        assert_eq!(last.net_installment(), dec!(0));
+        assert!(result.sac_table.total_out_of_pocket() < result.sac_table.total_paid);
+        assert_eq!(gross.price_table.total_out_of_pocket(), gross.price_table.total_paid);
+
+        assert!(calculate(&LoanConfig { employer_subsidy_monthly: Some(dec!(-1)), ..config }).is_err());
+    }
+
+    #[test]
+    fn test_apply_rate_promo() {
+        let price = calculate_price_table(dec!(100000), dec!(0.01).into(), 120).unwrap();
+        let curve = &price.amortization_curve;
+        let promo = apply_rate_promo(curve, 12, dec!(0.005), AmortizationSystem::Price).unwrap();
+
+        let original = &curve[11];
+        let month = &promo.amortization_curve[11];
+        assert_eq!(promo.interest_saved, (original.current_interest / dec!(2)).round_dp(2));
+        assert_eq!(month.installment(), original.installment());
+        assert_eq!(month.opening_balance(), original.opening_balance());
+        assert!(month.new_balance < original.new_balance);
+        assert_eq!(promo.amortization_curve[..11], curve[..11]);
+        assert!(total_interest(&promo.amortization_curve) < total_interest(curve));
+        assert_eq!(promo.amortization_curve.last().unwrap().new_balance, dec!(0));
+
+        let last = apply_rate_promo(curve, 120, dec!(0), AmortizationSystem::Price).unwrap();
+        assert_eq!(last.amortization_curve[119].current_interest, dec!(0));
+
+        assert!(apply_rate_promo(curve, 0, dec!(0.005), AmortizationSystem::Price).is_err());
+        assert!(apply_rate_promo(curve, 12, dec!(0.02), AmortizationSystem::Price).is_err());
+    }
+
+    #[test]
+    fn test_portfolio_outstanding_at() {
+        let loan = |total_amount: Decimal| {
+            calculate(&LoanConfig { total_amount, interest_per_year: dec!(12), total_months: 12, ..Default::default() })
+                .unwrap()
+        };
+        let loans = vec![(0, loan(dec!(12000))), (6, loan(dec!(24000)))];
+
+        assert_eq!(portfolio_outstanding_at(&loans, 0, AmortizationSystem::Sac), dec!(12000));
+        assert_eq!(portfolio_outstanding_at(&loans, 3, AmortizationSystem::Sac), dec!(9000));
+        assert_eq!(portfolio_outstanding_at(&loans, 6, AmortizationSystem::Sac), dec!(6000) + dec!(24000));
+        assert_eq!(portfolio_outstanding_at(&loans, 12, AmortizationSystem::Sac), dec!(12000));
+        assert_eq!(portfolio_outstanding_at(&loans, 30, AmortizationSystem::Sac), dec!(0));
+        assert_eq!(
+            portfolio_outstanding_at(&loans, 3, AmortizationSystem::Price),
+            loans[0].1.price_table.amortization_curve[2].new_balance
+        );
+    }
+
+    #[test]
+    fn test_apply_prepayment_schedule() {
+        let sac = calculate_sac_table(dec!(100000), dec!(0.01).into(), 120).unwrap();
+        let curve = &sac.amortization_curve;
+        let plan = [(24, dec!(5000)), (12, dec!(5000))];
+
+        let result =
+            apply_prepayment_schedule(curve, &plan, PrepaymentStrategy::ReduceTerm, AmortizationSystem::Sac).unwrap();
+        let first = apply_prepayment(curve, 12, dec!(5000), PrepaymentStrategy::ReduceTerm, AmortizationSystem::Sac)
+            .unwrap();
+        let both = apply_prepayment(&first, 24, dec!(5000), PrepaymentStrategy::ReduceTerm, AmortizationSystem::Sac)
+            .unwrap();
+
+        assert_eq!(result.amortization_curve, both);
+        assert_eq!(result.payoff_month, both.len() as u32);
+        assert!(result.payoff_month < 120);
+        assert_eq!(
+            result.interest_saved,
+            (total_interest(curve) - total_interest(&first)).round_dp(2)
+                + (total_interest(&first) - total_interest(&both)).round_dp(2)
+        );
+
+        let apply = |plan: &[(u32, Decimal)]| {
+            apply_prepayment_schedule(curve, plan, PrepaymentStrategy::ReduceTerm, AmortizationSystem::Sac)
+        };
+        assert_eq!(
+            apply(&[(500, dec!(1))]).unwrap_err().to_string(),
+            "Prepayments after the last installment (month 120) cannot be applied: 1 in month 500."
+        );
+        assert_eq!(
+            apply(&[(12, dec!(90000)), (100, dec!(1000)), (110, dec!(1000))]).unwrap_err().to_string(),
+            "Prepayments after the last installment (month 13) cannot be applied: 1000 in month 100, 1000 in month 110."
+        );
+        assert!(apply(&[(0, dec!(1))]).is_err());
+    }
+
+    #[test]
+    fn test_price_interest_principal_crossover() {
+        let price = calculate_price_table(dec!(100000), dec!(0.01).into(), 360).unwrap();
+        let month = price_interest_principal_crossover(&price).unwrap() as usize;
+        let curve = &price.amortization_curve;
+        assert!(curve[month - 1].current_amortization > curve[month - 1].current_interest);
+        assert!(curve[month - 2].current_amortization <= curve[month - 2].current_interest);
+
+        let short = calculate_price_table(dec!(100000), dec!(0.01).into(), 12).unwrap();
+        assert_eq!(price_interest_principal_crossover(&short), Some(1));
+
+        let mut never = price.clone();
+        never.amortization_curve.truncate(month - 1);
+        assert_eq!(price_interest_principal_crossover(&never), None);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_business_day_schedule() {
+        let date = |year, month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+        let mut calendar = BusinessDayCalendar::default();
+        assert!(!calendar.is_business_day(date(2025, 3, 1)));
+        assert!(calendar.is_business_day(date(2025, 3, 3)));
+
+        calendar.holidays.insert(date(2025, 4, 21));
+        // 2025-03-01 is a Saturday and 2025-04-21 (Tiradentes) a Monday.
+        let schedule = business_day_schedule(
+            dec!(12000),
+            dec!(12),
+            3,
+            AmortizationSystem::Sac,
+            DayCount::Monthly30,
+            date(2025, 2, 21),
+            &calendar,
+        )
+        .unwrap();
+        let due_dates: Vec<NaiveDate> = schedule.iter().map(|scheduled| scheduled.due_date).collect();
+        assert_eq!(due_dates, vec![date(2025, 2, 21), date(2025, 3, 21), date(2025, 4, 22)]);
+        let sac = calculate_sac_table(dec!(12000), MonthlyRate::from_annual_percent(dec!(12)).unwrap(), 3).unwrap();
+        assert_eq!(schedule[0].payment, sac.amortization_curve[0]);
+
+        let daily = business_day_schedule(
+            dec!(12000),
+            dec!(12),
+            3,
+            AmortizationSystem::Sac,
+            DayCount::Actual365,
+            date(2025, 2, 21),
+            &calendar,
+        )
+        .unwrap();
+        let balance_before_april = daily[1].payment.new_balance;
+        assert_eq!(daily[2].payment.current_interest, balance_before_april * dec!(0.12) * dec!(32) / dec!(365));
+        assert_eq!(daily[2].payment.new_balance, dec!(0));
+        let daily_price = business_day_schedule(
+            dec!(12000),
+            dec!(12),
+            3,
+            AmortizationSystem::Price,
+            DayCount::Actual365,
+            date(2025, 2, 21),
+            &calendar,
+        )
+        .unwrap();
+        let installments: Vec<Decimal> =
+            daily_price.iter().map(|scheduled| scheduled.payment.installment().round_dp(2)).collect();
+        assert_eq!(installments, vec![installments[0]; 3]);
+
+        let month_end = business_day_schedule(
+            dec!(12000),
+            dec!(12),
+            2,
+            AmortizationSystem::Price,
+            DayCount::Monthly30,
+            date(2025, 1, 31),
+            &calendar,
+        )
+        .unwrap();
+        assert_eq!(month_end[1].due_date, date(2025, 2, 28));
+
+        calendar.convention = BusinessDayConvention::Preceding;
+        assert_eq!(calendar.adjust(date(2025, 4, 21)), date(2025, 4, 18));
+    }
+
+    #[test]
+    fn test_refinance_analysis() {
+        let result =
+            refinance_analysis(dec!(100000), dec!(0.01), 120, dec!(0.009), 120, dec!(1500), AmortizationSystem::Sac).unwrap();
+        // Same term as `test_points_break_even`, so the break-even month matches it.
+        assert_eq!(result.break_even_month, Some(16));
+        // SAC interest is i * P * (n + 1) / 2, so the lower rate saves 0.001 * 100000 * 60.5.
+        assert_eq!(result.stay_total_cost, dec!(160500));
+        assert_eq!(result.refinance_total_cost, dec!(154450) + dec!(1500));
+        assert_eq!(result.savings, dec!(4550));
+        assert_eq!(result.amortization_curve.len(), 120);
+
+        // A longer term lowers the installments but costs more in total.
+        let longer =
+            refinance_analysis(dec!(100000), dec!(0.01), 60, dec!(0.009), 120, dec!(1000), AmortizationSystem::Price).unwrap();
+        // The installment drops by about 860 a month, which would cover the cost in the second
+        // month, but the new loan costs more in total so it never breaks even.
+        assert!(longer.savings < dec!(0));
+        assert_eq!(longer.break_even_month, None);
+
+        let never =
+            refinance_analysis(dec!(100000), dec!(0.01), 12, dec!(0.01), 12, dec!(1), AmortizationSystem::Price).unwrap();
+        assert_eq!(never.break_even_month, None);
+        assert!(refinance_analysis(dec!(100000), dec!(0.01), 0, dec!(0.009), 12, dec!(0), AmortizationSystem::Sac).is_err());
+    }
+
+    #[test]
+    fn test_cost_multiple() {
+        let result = calculate_debt_trajectory(DebtCalculationInput {
+            total_amount: dec!(12000),
+            interest_per_year: dec!(12),
+            down_payment_percent: dec!(0),
+            total_months: 12,
+            signing_to_first_payment_months: 0,
+        })
+        .unwrap();
+
+        assert_eq!(result.sac_table.cost_multiple(), dec!(12740.13) / dec!(12000));
+        assert_eq!(result.price_table.cost_multiple(), dec!(12752.94) / dec!(12000));
+        assert_eq!(result.price_table.cost_multiple().round_dp(4), dec!(1.0627));
     }
 }